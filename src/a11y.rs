@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use egui::accesskit::{CheckedState, Node, NodeId, Role, TreeUpdate};
+use egui::{Context, Id, RawInput, Response};
+
+// 读屏软件不会朗读悬停提示, 只有图标的控件需要单独设置名称
+pub trait Accessible {
+    fn accessible_name(self, name: impl Into<String>) -> Self;
+}
+
+impl Accessible for Response {
+    fn accessible_name(self, name: impl Into<String>) -> Self {
+        let name = name.into();
+        self.ctx
+            .accesskit_node_builder(self.id, |builder| builder.set_name(name));
+        self
+    }
+}
+
+// 把 add_contents 中的控件放到一个有名称的节点下, 如对话框.
+// egui 默认所有控件都直接挂在窗口根节点下
+pub fn group(ctx: &Context, id: Id, role: Role, name: &str, add_contents: impl FnOnce()) {
+    ctx.accesskit_node_builder(id, |builder| {
+        builder.set_role(role);
+        builder.set_name(name);
+    });
+    ctx.with_accessibility_parent(id, add_contents);
+}
+
+// 会被读屏软件聚焦的控件, 必须有名称
+fn is_interactive(role: Role) -> bool {
+    matches!(
+        role,
+        Role::Button
+            | Role::CheckBox
+            | Role::ColorWell
+            | Role::Link
+            | Role::PopupButton
+            | Role::RadioButton
+            | Role::Slider
+            | Role::SpinButton
+            | Role::TextField
+            | Role::ToggleButton
+    )
+}
+
+// 某一帧完整的 AccessKit 树
+pub struct AccessTree {
+    root: NodeId,
+    nodes: HashMap<NodeId, Node>,
+}
+
+impl AccessTree {
+    // egui 每帧都输出完整的树, 没有 tree 字段的增量更新不支持
+    pub fn from_update(update: TreeUpdate) -> Option<Self> {
+        Some(Self {
+            root: update.tree?.root,
+            nodes: update.nodes.into_iter().collect(),
+        })
+    }
+
+    // 无界面运行 run_ui 并返回最后一帧的树.
+    // 运行两帧, 窗口等需要上一帧的大小才能正确布局
+    pub fn capture(ctx: &Context, mut run_ui: impl FnMut(&Context)) -> Option<Self> {
+        ctx.enable_accesskit();
+        let mut update = None;
+        for _ in 0..2 {
+            let raw = RawInput {
+                time: Some(ctx.input(|i| i.time) + 1.0 / 60.0),
+                ..Default::default()
+            };
+            update = ctx.run(raw, &mut run_ui).platform_output.accesskit_update;
+        }
+        Self::from_update(update?)
+    }
+
+    pub fn root(&self) -> NodeId {
+        self.root
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&Node> {
+        self.nodes.get(&id)
+    }
+
+    // 深度优先遍历, 父节点在子节点之前
+    pub fn nodes(&self) -> Vec<(NodeId, &Node)> {
+        let mut nodes = Vec::with_capacity(self.nodes.len());
+        let mut stack = vec![self.root];
+        while let Some(id) = stack.pop() {
+            let Some(node) = self.nodes.get(&id) else {
+                continue;
+            };
+            nodes.push((id, node));
+            stack.extend(node.children().iter().rev());
+        }
+        nodes
+    }
+
+    // 读屏软件朗读的名称: 与 aria-labelledby 一样, labelled_by 指向的标签优先
+    pub fn label(&self, node: &Node) -> Option<String> {
+        let labels: Vec<&str> = node
+            .labelled_by()
+            .iter()
+            .filter_map(|id| self.nodes.get(id)?.name())
+            .collect();
+        if !labels.is_empty() {
+            return Some(labels.join(" "));
+        }
+        node.name()
+            .filter(|name| !name.is_empty())
+            .map(str::to_owned)
+    }
+
+    // 查找角色相同且名称包含 name 的节点
+    pub fn find(&self, role: Role, name: &str) -> Option<NodeId> {
+        self.nodes()
+            .into_iter()
+            .find(|(_, node)| {
+                node.role() == role && self.label(node).is_some_and(|label| label.contains(name))
+            })
+            .map(|(id, _)| id)
+    }
+
+    // 没有名称的可交互控件, 读屏软件只能读出控件类型
+    pub fn unlabelled(&self) -> Vec<(NodeId, Role)> {
+        self.nodes()
+            .into_iter()
+            .filter(|(_, node)| is_interactive(node.role()) && self.label(node).is_none())
+            .map(|(id, node)| (id, node.role()))
+            .collect()
+    }
+
+    // 缩进的文本形式, 每行一个节点
+    pub fn dump(&self) -> String {
+        let mut out = String::new();
+        self.dump_node(&mut out, self.root, 0);
+        out
+    }
+
+    fn dump_node(&self, out: &mut String, id: NodeId, depth: usize) {
+        let Some(node) = self.nodes.get(&id) else {
+            return;
+        };
+        write!(out, "{:indent$}{:?}", "", node.role(), indent = depth * 2).ok();
+        if let Some(label) = self.label(node) {
+            write!(out, " {label:?}").ok();
+        }
+        if let Some(value) = node.value() {
+            write!(out, " value={value:?}").ok();
+        } else if let Some(value) = node.numeric_value() {
+            write!(out, " value={value}").ok();
+        }
+        match node.checked_state() {
+            Some(CheckedState::True) => out.push_str(" [checked]"),
+            Some(CheckedState::Mixed) => out.push_str(" [mixed]"),
+            _ => {}
+        }
+        if node.is_disabled() {
+            out.push_str(" [disabled]");
+        }
+        out.push('\n');
+        // 文本框内部的每一行文字对读屏没有额外信息
+        if node.role() == Role::TextField {
+            return;
+        }
+        for child in node.children() {
+            self.dump_node(out, *child, depth + 1);
+        }
+    }
+}
@@ -1,4 +1,25 @@
-use egui::{FontFamily, FontId, Key, RichText, ScrollArea, TextStyle};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use egui::accesskit::Role;
+use egui::{FontFamily, FontId, Key, KeyboardShortcut, Modifiers, RichText, TextStyle};
+
+use crate::a11y::{AccessTree, Accessible};
+use crate::dialog::{DialogId, DialogResponse, Dialogs};
+use crate::editor::Editor;
+use crate::form::{Binding, Field, FieldValue, Form, Validator};
+use crate::highlight::Language;
+use crate::history::History;
+use crate::i18n::{self, tr, tr_args, tr_plural, Locale};
+use crate::ime;
+use crate::input_log::InputLog;
+use crate::markdown::MarkdownViewer;
+use crate::session::{self, Player, Recorder, Session};
+use crate::settings::DisplaySettings;
+use crate::shortcuts::{CommandPalette, Shortcuts};
+use crate::task::{Cancelled, TaskContext, TaskError, TaskHandle, TaskRunner};
+use crate::theme::ThemeSettings;
+use crate::toast::Toasts;
 
 // 初始化字体文件
 fn setup_custom_fonts(ctx: &egui::Context) {
@@ -26,25 +47,43 @@ fn setup_custom_fonts(ctx: &egui::Context) {
         .or_default()
         .push("my_font".to_owned());
 
+    // 中文字体作为两种字体的最后备选
+    if let Some(font) = i18n::load_cjk_font() {
+        fonts
+            .font_data
+            .insert("cjk".to_owned(), egui::FontData::from_owned(font));
+        for family in [egui::FontFamily::Proportional, egui::FontFamily::Monospace] {
+            fonts
+                .families
+                .entry(family)
+                .or_default()
+                .push("cjk".to_owned());
+        }
+    }
+
     // Tell egui to use these fonts:
     ctx.set_fonts(fonts);
 }
 
 // 初始化字体
 #[inline]
-fn heading2() -> TextStyle {
+pub(crate) fn heading2() -> TextStyle {
     TextStyle::Name("Heading2".into())
 }
 
 #[inline]
-fn heading3() -> TextStyle {
+pub(crate) fn heading3() -> TextStyle {
     TextStyle::Name("ContextHeading".into())
 }
 
-fn configure_text_styles(ctx: &egui::Context) {
+// 主题的配色, 显示设置在此基础上调整字号和对比度
+fn base_style(visuals: egui::Visuals) -> egui::Style {
     use FontFamily::{Monospace, Proportional};
 
-    let mut style = (*ctx.style()).clone();
+    let mut style = egui::Style {
+        visuals,
+        ..Default::default()
+    };
     style.text_styles = [
         (TextStyle::Heading, FontId::new(25.0, Proportional)), // heading
         (heading2(), FontId::new(22.0, Proportional)),
@@ -55,7 +94,7 @@ fn configure_text_styles(ctx: &egui::Context) {
         (TextStyle::Small, FontId::new(8.0, Proportional)),
     ]
     .into();
-    ctx.set_style(style);
+    style
 }
 
 // 预览拖拽的文件
@@ -66,7 +105,7 @@ fn preview_files_being_dropped(ctx: &egui::Context) {
     if !ctx.input(|i| i.raw.hovered_files.is_empty()) {
         // 遮罩层文字
         let text = ctx.input(|i| {
-            let mut text = "Dropping files:\n".to_owned();
+            let mut text = format!("{}\n", tr("dropping-files"));
             for file in &i.raw.hovered_files {
                 if let Some(path) = &file.path {
                     write!(text, "\n{}", path.display()).ok();
@@ -95,8 +134,8 @@ fn preview_files_being_dropped(ctx: &egui::Context) {
     }
 }
 
-// puffin
-pub fn start_puffin_server() {
+// puffin, 启动失败时返回错误信息, 由调用方决定如何展示
+pub fn start_puffin_server() -> Result<(), String> {
     puffin::set_scopes_on(true); // tell puffin to collect data
 
     match puffin_http::Server::new("0.0.0.0:8585") {
@@ -107,49 +146,369 @@ pub fn start_puffin_server() {
             // it to keep running. Dropping it closes the server, so let's not drop it!
             #[allow(clippy::mem_forget)]
             std::mem::forget(puffin_server);
+            Ok(())
         }
         Err(err) => {
-            eprintln!("Failed to start puffin server: {err}");
+            log::error!("Failed to start puffin server: {err}");
+            Err(format!("Failed to start puffin server: {err}"))
         }
-    };
+    }
 }
 
 // 内容区
 fn content(app: &mut MyApp, ui: &mut egui::Ui) {
-    ui.heading("egui demo");
-    ui.add_space(15.);
-
-    // 同一水平区域
     ui.horizontal(|ui| {
-        let name_label = ui.label("Your name: ");
-        ui.text_edit_singleline(&mut app.name)
-            .labelled_by(name_label.id);
+        ui.heading("egui demo");
+        ui.toggle_value(&mut app.show_markdown, format!("📖 {}", tr("help")));
+        ui.toggle_value(&mut app.show_history, format!("🕘 {}", tr("history")));
+        ui.toggle_value(&mut app.show_shortcuts, format!("⌨ {}", tr("shortcuts")));
+        ui.toggle_value(&mut app.show_input_log, format!("🖱 {}", tr("input")));
+        ui.toggle_value(&mut app.show_settings, format!("⚙ {}", tr("settings")));
+        let mut locale = i18n::locale();
+        egui::ComboBox::from_id_source("locale")
+            .selected_text(format!("🌐 {}", locale.native_name()))
+            .show_ui(ui, |ui| {
+                for option in Locale::ALL {
+                    ui.selectable_value(&mut locale, option, option.native_name());
+                }
+            })
+            .response
+            .accessible_name(tr("language"));
+        if locale != i18n::locale() {
+            i18n::set_locale(locale);
+        }
     });
-    // Slider
-    ui.add(egui::Slider::new(&mut app.age, 0..=120).text("age"));
-    if ui.button("Click each year").clicked() {
+    ui.add_space(15.);
+
+    // 姓名和年龄表单, 提交时保存
+    let response = app.profile.ui(
+        ui,
+        &mut [Binding::Text(&mut app.name), Binding::U32(&mut app.age)],
+    );
+    if response.submitted {
+        app.save_changes();
+    }
+    if app.is_dirty() {
+        ui.label(
+            RichText::new(format!("● {}", tr("unsaved-changes"))).color(ui.visuals().warn_fg_color),
+        );
+    }
+    if ui.button(tr("click-each-year")).clicked() {
         app.age += 1;
+        app.profile.touch("age");
+        app.edit_label = Some("click-each-year");
     }
-    ui.label(format!("Hello '{}', age {}", app.name, app.age));
+    ui.label(tr_plural("hello", app.age as i64, &[("name", &app.name)]));
 
     ui.add_space(15.);
     ui.monospace(LOREM_IPSUM);
     ui.add_space(15.);
-    ui.label(RichText::new("Sub Heading").text_style(heading2()).strong());
+    ui.label(
+        RichText::new(tr("sub-heading"))
+            .text_style(heading2())
+            .strong(),
+    );
     ui.add_space(15.);
 
     // 文本编辑器（使用默认文本）
-    ui.text_edit_multiline(&mut app.text);
+    app.editor.ui(ui, &mut app.toasts);
 }
 
-pub struct MyApp {
+fn profile_form() -> Form {
+    Form::new("profile")
+        .field(
+            Field::text("name", "profile-name")
+                .hint("profile-name-hint")
+                .validator(Validator::Required)
+                .validator(Validator::length(2, 32))
+                .validator(Validator::pattern(
+                    r"^[\p{L} .'-]+$",
+                    "validation-name-pattern",
+                )),
+        )
+        .field(
+            Field::slider("age", "profile-age", 0.0..=120.0)
+                .validator(Validator::range(0.0..=120.0)),
+        )
+        .submit_label("save")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AppCommand {
+    CommandPalette,
+    OpenFile,
+    Save,
+    Undo,
+    Redo,
+    Find,
+    ToggleTheme,
+    ToggleHistory,
+    ToggleHelp,
+    ToggleInputLog,
+    RecordSession,
+    ReplaySession,
+    KeyboardShortcuts,
+    ZoomIn,
+    ZoomOut,
+    ZoomReset,
+    ToggleSettings,
+    ToggleTasks,
+    AnalyzeText,
+    Screenshot,
+    Quit,
+}
+
+// 主界面必须能被读屏软件找到的控件, 名称为翻译 key
+const ACCESSIBLE_CONTROLS: &[(Role, &str)] = &[
+    (Role::ToggleButton, "help"),
+    (Role::ToggleButton, "history"),
+    (Role::ToggleButton, "shortcuts"),
+    (Role::ToggleButton, "input"),
+    (Role::ToggleButton, "settings"),
+    (Role::PopupButton, "language"),
+    (Role::TextField, "profile-name"),
+    (Role::Slider, "profile-age"),
+    (Role::Button, "save"),
+    (Role::Button, "click-each-year"),
+    (Role::Button, "undo"),
+    (Role::Button, "redo"),
+    (Role::TextField, "editor-text"),
+    (Role::Button, "command-open-file"),
+];
+
+// 快捷键配置在存储中的键
+const SHORTCUTS_KEY: &str = "shortcuts";
+const LOCALE_KEY: &str = "locale";
+const DISPLAY_KEY: &str = "display_settings";
+const THEME_KEY: &str = "theme";
+
+fn app_shortcuts() -> Shortcuts<AppCommand> {
+    let command = |key| Some(KeyboardShortcut::new(Modifiers::COMMAND, key));
+    Shortcuts::default()
+        .register(
+            AppCommand::CommandPalette,
+            "command_palette",
+            "command-palette",
+            Some(KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::P,
+            )),
+        )
+        .register(
+            AppCommand::OpenFile,
+            "open_file",
+            "command-open-file",
+            command(Key::O),
+        )
+        .register(AppCommand::Save, "save", "command-save", command(Key::S))
+        .register(AppCommand::Undo, "undo", "undo", command(Key::Z))
+        .register(
+            AppCommand::Redo,
+            "redo",
+            "redo",
+            Some(KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::Z,
+            )),
+        )
+        .register(AppCommand::Find, "find", "command-find", command(Key::F))
+        .register(
+            AppCommand::ToggleTheme,
+            "toggle_theme",
+            "command-toggle-theme",
+            command(Key::T),
+        )
+        .register(
+            AppCommand::ToggleHistory,
+            "toggle_history",
+            "command-toggle-history",
+            command(Key::H),
+        )
+        .register(
+            AppCommand::ToggleHelp,
+            "toggle_help",
+            "command-toggle-help",
+            Some(KeyboardShortcut::new(Modifiers::NONE, Key::F1)),
+        )
+        .register(
+            AppCommand::ToggleInputLog,
+            "toggle_input_log",
+            "command-toggle-input-log",
+            Some(KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::I,
+            )),
+        )
+        .register(
+            AppCommand::RecordSession,
+            "record_session",
+            "command-record-session",
+            Some(KeyboardShortcut::new(
+                Modifiers::COMMAND | Modifiers::SHIFT,
+                Key::R,
+            )),
+        )
+        .register(
+            AppCommand::ReplaySession,
+            "replay_session",
+            "command-replay-session",
+            None,
+        )
+        .register(
+            AppCommand::KeyboardShortcuts,
+            "keyboard_shortcuts",
+            "command-keyboard-shortcuts",
+            None,
+        )
+        .register(
+            AppCommand::ZoomIn,
+            "zoom_in",
+            "command-zoom-in",
+            command(Key::PlusEquals),
+        )
+        .register(
+            AppCommand::ZoomOut,
+            "zoom_out",
+            "command-zoom-out",
+            command(Key::Minus),
+        )
+        .register(
+            AppCommand::ZoomReset,
+            "zoom_reset",
+            "command-zoom-reset",
+            command(Key::Num0),
+        )
+        .register(
+            AppCommand::ToggleSettings,
+            "toggle_settings",
+            "command-toggle-settings",
+            None,
+        )
+        .register(
+            AppCommand::ToggleTasks,
+            "toggle_tasks",
+            "command-toggle-tasks",
+            None,
+        )
+        .register(
+            AppCommand::AnalyzeText,
+            "analyze_text",
+            "command-analyze-text",
+            None,
+        )
+        .register(
+            AppCommand::Screenshot,
+            "screenshot",
+            "command-screenshot",
+            Some(KeyboardShortcut::new(Modifiers::NONE, Key::F12)),
+        )
+        .register(AppCommand::Quit, "quit", "command-quit", command(Key::Q))
+}
+
+// 后台统计的文本信息
+struct TextStats {
+    lines: usize,
+    words: usize,
+    chars: usize,
+    // 出现最多的几个词
+    top_words: Vec<(String, usize)>,
+}
+
+fn analyze(text: &str, task: &TaskContext<()>) -> Result<TextStats, Cancelled> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut words = 0;
+    for (index, line) in lines.iter().enumerate() {
+        // 大文件有几十万行, 每一千行检查一次取消并报告进度
+        if index % 1000 == 0 {
+            task.checkpoint()?;
+            task.progress(index as f32 / lines.len() as f32, ());
+        }
+        for word in line
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            words += 1;
+            *counts.entry(word.to_lowercase()).or_default() += 1;
+        }
+    }
+    let mut top_words: Vec<(String, usize)> = counts.into_iter().collect();
+    top_words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    top_words.truncate(5);
+    Ok(TextStats {
+        lines: lines.len(),
+        words,
+        chars: text.chars().count(),
+        top_words,
+    })
+}
+
+// 可编辑字段的快照, 与当前值比较判断是否有未保存的修改
+#[derive(Clone, PartialEq, serde::Deserialize, serde::Serialize)]
+struct Snapshot {
     name: String,
     age: u32,
-    // 默认文本
     text: String,
+}
+
+impl Snapshot {
+    // 历史记录的内存估算
+    fn size(&self) -> usize {
+        std::mem::size_of::<Self>() + self.name.len() + self.text.len()
+    }
+}
+
+pub struct MyApp {
+    name: String,
+    age: u32,
+    // 文本编辑器（默认文本）
+    editor: Editor,
+    // 最近一次保存的字段
+    saved: Snapshot,
+    // 所有字段修改的撤销历史
+    history: History<Snapshot>,
+    show_history: bool,
+    // 本帧修改的名称, 设置后不与之前的输入合并
+    edit_label: Option<&'static str>,
     allowed_to_close: bool,
-    // 当前是否打开确认对话框
-    show_confirmation_dialog: bool,
+    shortcuts: Shortcuts<AppCommand>,
+    palette: CommandPalette,
+    show_shortcuts: bool,
+    // 输入事件调试窗口
+    input_log: InputLog,
+    show_input_log: bool,
+    // 界面缩放、高对比度等显示设置
+    display: DisplaySettings,
+    show_settings: bool,
+    // 后台任务和任务列表窗口
+    tasks: TaskRunner,
+    show_tasks: bool,
+    analysis: Option<TaskHandle<(), TextStats>>,
+    // 深色/浅色主题和配色方案
+    theme: ThemeSettings,
+    // 系统的深色/浅色设置, 无界面运行或系统不支持时没有
+    system_theme: Option<eframe::Theme>,
+    // 系统的缩放, 无界面运行时没有
+    native_pixels_per_point: Option<f32>,
+    // 显示设置或主题修改后, 在下一帧开始时重新应用
+    display_dirty: bool,
+    // 截图在 post_rendering 中保存
+    screenshot_requested: bool,
+    // 需要 eframe::Frame 的操作在 update 结束时执行
+    close_requested: bool,
+    // 输入会话的录制和回放
+    recorder: Option<Recorder>,
+    player: Option<Player>,
+    replay_speed: f64,
+    // 当前打开的关闭确认对话框
+    close_dialog: Option<DialogId>,
+    // 姓名和年龄的表单定义及校验状态
+    profile: Form,
+    dialogs: Dialogs,
+    toasts: Toasts,
+    // Markdown 文档窗口
+    markdown: MarkdownViewer,
+    show_markdown: bool,
     // 拖拽的文件
     dropped_files: Vec<egui::DroppedFile>,
     // 选择的文件路径
@@ -158,22 +517,479 @@ pub struct MyApp {
 
 impl MyApp {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        setup_custom_fonts(&cc.egui_ctx);
-        configure_text_styles(&cc.egui_ctx);
-        Self {
-            ..Default::default()
+        // 没有保存过语言时跟随系统
+        let locale = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, LOCALE_KEY))
+            .unwrap_or_else(Locale::from_env);
+        i18n::set_locale(locale);
+        // 翻译缺失时会回退到英文, 这里提示一下方便补全
+        for locale in Locale::ALL {
+            let missing = i18n::missing_keys(locale);
+            if !missing.is_empty() {
+                log::warn!(
+                    "{} catalog is missing: {}",
+                    locale.code(),
+                    missing.join(", ")
+                );
+            }
+        }
+        let mut app = Self::with_context(&cc.egui_ctx);
+        if let Some(display) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, DISPLAY_KEY))
+        {
+            app.display = display;
+        }
+        if let Some(theme) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, THEME_KEY))
+        {
+            app.theme = theme;
+        }
+        // 恢复上次保存的字段
+        if let Some(saved) = cc
+            .storage
+            .and_then(|storage| eframe::get_value::<Snapshot>(storage, eframe::APP_KEY))
+        {
+            app.restore(&saved);
+            app.history = History::new(saved.clone(), Snapshot::size);
+            app.saved = saved;
+        }
+        if let Some(config) = cc
+            .storage
+            .and_then(|storage| eframe::get_value(storage, SHORTCUTS_KEY))
+        {
+            for error in app.shortcuts.apply_config(&config) {
+                app.toasts
+                    .warning(tr_args("shortcut-setting-ignored", &[("error", &error)]));
+            }
+        }
+        app
+    }
+
+    // 不依赖 eframe 创建, 用于无界面回放
+    pub fn with_context(ctx: &egui::Context) -> Self {
+        setup_custom_fonts(ctx);
+        Self::default()
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn age(&self) -> u32 {
+        self.age
+    }
+
+    pub fn text(&self) -> &str {
+        self.editor.text()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            name: self.name.clone(),
+            age: self.age,
+            text: self.editor.text().to_owned(),
+        }
+    }
+
+    fn restore(&mut self, snapshot: &Snapshot) {
+        self.name = snapshot.name.clone();
+        self.age = snapshot.age;
+        // set_text 会清空编辑器自己的撤销栈, 文本没变时不调用
+        if self.editor.text() != snapshot.text {
+            self.editor.set_text(snapshot.text.clone());
+        }
+    }
+
+    // 与历史中的当前状态比较, 有修改时记录一步
+    fn record_history(&mut self, now: f64) {
+        let current = self.history.current();
+        let (label, coalesce) = if current.name != self.name {
+            ("history-edit-name", true)
+        } else if current.age != self.age {
+            ("history-change-age", true)
+        } else if current.text != self.editor.text() {
+            ("history-edit-text", true)
+        } else {
+            self.edit_label = None;
+            return;
+        };
+        // 按钮等一次性操作单独成步
+        let (label, coalesce) = match self.edit_label.take() {
+            Some(label) => (label, false),
+            None => (label, coalesce),
+        };
+        self.history.record(label, self.snapshot(), now, coalesce);
+    }
+
+    fn jump_history(&mut self, index: usize) {
+        if let Some(snapshot) = self.history.jump(index) {
+            let snapshot = snapshot.clone();
+            self.restore(&snapshot);
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if self.history.can_undo() {
+            self.jump_history(self.history.cursor() - 1);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if self.history.can_redo() {
+            self.jump_history(self.history.cursor() + 1);
+        }
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.saved.name != self.name
+            || self.saved.age != self.age
+            || self.saved.text != self.editor.text()
+    }
+
+    // 表单校验失败时不保存, 返回是否保存成功
+    pub fn save_changes(&mut self) -> bool {
+        let values = [
+            FieldValue::Text(&self.name),
+            FieldValue::Number(self.age as f64),
+        ];
+        if !self.profile.validate_all(&values) {
+            self.toasts.warning(tr("save-invalid"));
+            return false;
+        }
+        self.saved = self.snapshot();
+        self.toasts.success(tr("save-done"));
+        true
+    }
+
+    fn open_file(&mut self) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            self.picked_path = Some(path.display().to_string());
+        }
+    }
+
+    // 对话框或命令面板打开时不响应快捷键
+    fn commands_enabled(&self) -> bool {
+        !(self.dialogs.is_open() || self.palette.is_open())
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recorder.is_some()
+    }
+
+    pub fn start_recording(&mut self) {
+        self.player = None;
+        self.recorder = Some(Recorder::default());
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Session> {
+        self.recorder.take().map(Recorder::finish)
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.player.is_some()
+    }
+
+    pub fn replay(&mut self, session: Session, speed: f64) {
+        self.recorder = None;
+        self.player = Some(Player::new(session, speed));
+    }
+
+    fn toggle_recording(&mut self) {
+        let Some(session) = self.stop_recording() else {
+            self.start_recording();
+            return;
+        };
+        if session.is_empty() {
+            self.toasts.info(tr("session-empty"));
+            return;
+        }
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Session", &["json"])
+            .set_file_name("session.json")
+            .save_file()
+        {
+            match session.save(&path) {
+                Ok(()) => self.toasts.success(tr_plural(
+                    "session-saved",
+                    session.len() as i64,
+                    &[("path", &path.display())],
+                )),
+                Err(err) => self.toasts.error(tr_args(
+                    "file-save-failed",
+                    &[("path", &path.display()), ("error", &err)],
+                )),
+            }
+        }
+    }
+
+    fn pick_replay(&mut self) {
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("Session", &["json"])
+            .pick_file()
+        {
+            match Session::load(&path) {
+                Ok(session) => self.replay(session, self.replay_speed),
+                Err(err) => self.toasts.error(tr_args(
+                    "file-open-failed",
+                    &[("path", &path.display()), ("error", &err)],
+                )),
+            }
         }
     }
+
+    // 回放时用录制的输入替换本帧输入, 录制时保存本帧输入
+    fn session_frame(&mut self, ctx: &egui::Context) {
+        if let Some(player) = &mut self.player {
+            if ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Escape)) {
+                self.player = None;
+                session::lock_focus(ctx, false);
+                self.toasts.info(tr("session-replay-stopped"));
+            } else if player.is_finished() {
+                self.player = None;
+                session::lock_focus(ctx, false);
+                self.toasts.success(tr("session-replay-finished"));
+            } else {
+                player.apply(ctx);
+            }
+        }
+        if let Some(recorder) = &mut self.recorder {
+            ctx.input(|i| recorder.record(&i.raw, i.time));
+        }
+    }
+
+    fn session_status(&self, ctx: &egui::Context) {
+        let text = if let Some(recorder) = &self.recorder {
+            RichText::new(format!(
+                "⏺ {}",
+                tr_plural("session-recording", recorder.len() as i64, &[])
+            ))
+            .color(ctx.style().visuals.error_fg_color)
+        } else if let Some(player) = &self.player {
+            let (done, total) = player.progress();
+            RichText::new(format!(
+                "▶ {}",
+                tr_args(
+                    "session-replaying",
+                    &[
+                        ("done", &done),
+                        ("total", &total),
+                        ("speed", &player.speed())
+                    ],
+                )
+            ))
+            .color(ctx.style().visuals.warn_fg_color)
+        } else {
+            return;
+        };
+        egui::Area::new("session_status")
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 4.0))
+            .interactable(false)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| ui.label(text));
+            });
+    }
+
+    fn session_ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let label = if self.is_recording() {
+                format!("⏹ {}", tr("session-stop"))
+            } else {
+                format!("⏺ {}", tr("session-record"))
+            };
+            if ui.button(label).clicked() {
+                self.toggle_recording();
+            }
+            if ui
+                .add_enabled(
+                    !self.is_recording(),
+                    egui::Button::new(format!("▶ {}", tr("session-replay"))),
+                )
+                .clicked()
+            {
+                self.pick_replay();
+            }
+            ui.label(tr("session-speed"));
+            ui.add(
+                egui::DragValue::new(&mut self.replay_speed)
+                    .clamp_range(0.1..=10.0)
+                    .speed(0.05)
+                    .suffix("×"),
+            );
+        });
+    }
+
+    fn run_command(&mut self, ctx: &egui::Context, command: AppCommand) {
+        match command {
+            AppCommand::CommandPalette => self.palette.open(),
+            AppCommand::OpenFile => self.open_file(),
+            // 编辑器有焦点时, 撤销、查找和保存文件交给编辑器
+            AppCommand::Save if self.editor.has_focus() && self.editor.path().is_some() => {
+                self.editor.save(&mut self.toasts);
+            }
+            AppCommand::Save => {
+                self.save_changes();
+            }
+            AppCommand::Undo if self.editor.has_focus() => self.editor.undo(),
+            AppCommand::Undo => self.undo(),
+            AppCommand::Redo if self.editor.has_focus() => self.editor.redo(),
+            AppCommand::Redo => self.redo(),
+            AppCommand::Find => self.editor.open_find(),
+            AppCommand::ToggleTheme => {
+                self.theme.toggle(self.system_theme);
+                self.display_dirty = true;
+            }
+            AppCommand::ToggleHistory => self.show_history = !self.show_history,
+            AppCommand::ToggleHelp => self.show_markdown = !self.show_markdown,
+            AppCommand::ToggleInputLog => self.show_input_log = !self.show_input_log,
+            AppCommand::RecordSession => self.toggle_recording(),
+            AppCommand::ReplaySession => self.pick_replay(),
+            AppCommand::KeyboardShortcuts => self.show_shortcuts = true,
+            AppCommand::ZoomIn => {
+                self.display.zoom_in();
+                self.display_dirty = true;
+            }
+            AppCommand::ZoomOut => {
+                self.display.zoom_out();
+                self.display_dirty = true;
+            }
+            AppCommand::ZoomReset => {
+                self.display.reset_zoom();
+                self.display_dirty = true;
+            }
+            AppCommand::ToggleSettings => self.show_settings = !self.show_settings,
+            AppCommand::ToggleTasks => self.show_tasks = !self.show_tasks,
+            AppCommand::AnalyzeText => self.analyze_text(ctx),
+            AppCommand::Screenshot => self.screenshot_requested = true,
+            // 经过 on_close_event, 未保存时会弹出确认框
+            AppCommand::Quit => self.close_requested = true,
+        }
+    }
+
+    // 在后台统计编辑器文本, 之前未完成的统计被取消
+    fn analyze_text(&mut self, ctx: &egui::Context) {
+        if let Some(analysis) = &self.analysis {
+            analysis.cancel();
+        }
+        let text = self.editor.text().to_owned();
+        self.analysis = Some(
+            self.tasks
+                .spawn(ctx, tr("command-analyze-text"), move |task| {
+                    analyze(&text, task)
+                }),
+        );
+    }
+
+    fn poll_analysis(&mut self) {
+        let Some(result) = self.analysis.as_mut().and_then(TaskHandle::try_result) else {
+            return;
+        };
+        self.analysis = None;
+        match result {
+            Ok(stats) => {
+                let top: Vec<String> = stats
+                    .top_words
+                    .iter()
+                    .map(|(word, count)| format!("{word} ×{count}"))
+                    .collect();
+                self.toasts.info(tr_args(
+                    "analysis-done",
+                    &[
+                        ("lines", &stats.lines),
+                        ("words", &stats.words),
+                        ("chars", &stats.chars),
+                        ("top", &top.join(", ")),
+                    ],
+                ));
+            }
+            Err(TaskError::Cancelled) => self.toasts.info(tr("analysis-cancelled")),
+            Err(TaskError::Panicked(message)) => self.toasts.error(message),
+        }
+    }
+
+    pub fn dialogs_mut(&mut self) -> &mut Dialogs {
+        &mut self.dialogs
+    }
+
+    pub fn toasts_mut(&mut self) -> &mut Toasts {
+        &mut self.toasts
+    }
+
+    // 检查主界面的 AccessKit 树, 返回缺少的控件和没有名称的控件
+    pub fn check_accessibility(tree: &AccessTree) -> Vec<String> {
+        let mut problems: Vec<String> = ACCESSIBLE_CONTROLS
+            .iter()
+            .filter(|(role, key)| tree.find(*role, tr(key)).is_none())
+            .map(|(role, key)| format!("missing {role:?} {:?}", tr(key)))
+            .collect();
+        problems.extend(
+            tree.unlabelled()
+                .into_iter()
+                .map(|(id, role)| format!("{role:?} {id:?} has no accessible name")),
+        );
+        problems
+    }
+}
+
+// 运行时查找 assets 目录: 先从可执行文件所在目录向上找
+// (cargo run 时可执行文件在 target/debug 下), 再找当前工作目录
+fn assets_dir() -> Option<PathBuf> {
+    let exe = std::env::current_exe().ok();
+    let from_exe = exe
+        .iter()
+        .flat_map(|exe| exe.ancestors().skip(1))
+        .map(|dir| dir.join("assets"));
+    let from_cwd = std::env::current_dir().ok().map(|dir| dir.join("assets"));
+    from_exe.chain(from_cwd).find(|dir| dir.is_dir())
 }
 
 impl Default for MyApp {
     fn default() -> Self {
+        let name = "Zzrk".to_owned();
+        let age = 18;
+        let text = "Edit this text field if you want".to_owned();
+        let saved = Snapshot {
+            name: name.clone(),
+            age,
+            text: text.clone(),
+        };
         Self {
-            name: "Zzrk".to_owned(),
-            age: 18,
-            text: "Edit this text field if you want".to_owned(),
+            history: History::new(saved.clone(), Snapshot::size),
+            show_history: false,
+            edit_label: None,
+            saved,
+            name,
+            age,
+            editor: Editor::new("editor", text),
             allowed_to_close: false,
-            show_confirmation_dialog: false,
+            shortcuts: app_shortcuts(),
+            palette: CommandPalette::default(),
+            show_shortcuts: false,
+            input_log: InputLog::default(),
+            show_input_log: false,
+            display: DisplaySettings::default(),
+            show_settings: false,
+            tasks: TaskRunner::default(),
+            show_tasks: false,
+            analysis: None,
+            theme: ThemeSettings::default(),
+            system_theme: None,
+            native_pixels_per_point: None,
+            display_dirty: true,
+            screenshot_requested: false,
+            close_requested: false,
+            recorder: None,
+            player: None,
+            replay_speed: 1.0,
+            close_dialog: None,
+            profile: profile_form(),
+            dialogs: Dialogs::default(),
+            toasts: Toasts::default(),
+            markdown: MarkdownViewer::new(include_str!("../assets/help.md"), assets_dir()),
+            show_markdown: false,
             dropped_files: Vec::new(),
             picked_path: None,
         }
@@ -182,24 +998,139 @@ impl Default for MyApp {
 
 impl eframe::App for MyApp {
     fn on_close_event(&mut self) -> bool {
-        self.show_confirmation_dialog = true;
-        self.allowed_to_close
+        // 没有未保存的修改时直接退出
+        if self.allowed_to_close || !self.is_dirty() {
+            return true;
+        }
+        if self.close_dialog.is_none() {
+            self.close_dialog = Some(self.dialogs.confirm(
+                tr("unsaved-title"),
+                tr("unsaved-message"),
+                &[tr("save"), tr("discard"), tr("cancel")],
+            ));
+        }
+        false
+    }
+
+    // 只持久化已保存的字段, 放弃的修改不会被写入
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, eframe::APP_KEY, &self.saved);
+        eframe::set_value(storage, SHORTCUTS_KEY, &self.shortcuts.to_config());
+        eframe::set_value(storage, LOCALE_KEY, &i18n::locale());
+        eframe::set_value(storage, DISPLAY_KEY, &self.display);
+        eframe::set_value(storage, THEME_KEY, &self.theme);
+    }
+
+    fn post_rendering(&mut self, _window_size: [u32; 2], frame: &eframe::Frame) {
+        if !self.screenshot_requested {
+            return;
+        }
+        let Some(screenshot) = frame.screenshot() else {
+            return;
+        };
+        self.screenshot_requested = false;
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let path = format!("screenshot-{secs}.png");
+        match image::save_buffer(
+            &path,
+            screenshot.as_raw(),
+            screenshot.width() as u32,
+            screenshot.height() as u32,
+            image::ColorType::Rgba8,
+        ) {
+            Ok(()) => self
+                .toasts
+                .success(tr_args("file-saved", &[("path", &path)])),
+            Err(err) => self.toasts.error(tr_args(
+                "file-save-failed",
+                &[("path", &path), ("error", &err)],
+            )),
+        }
     }
 
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // 窗口移动到缩放不同的显示器时需要重新计算
+        let native_pixels_per_point = frame.info().native_pixels_per_point;
+        if native_pixels_per_point != self.native_pixels_per_point {
+            self.native_pixels_per_point = native_pixels_per_point;
+            self.display_dirty = true;
+        }
+        // 系统主题变化时 eframe 会直接替换 visuals, 需要重新应用主题和显示设置
+        let system_theme = frame.info().system_theme;
+        if system_theme != self.system_theme {
+            self.system_theme = system_theme;
+            self.display_dirty = true;
+        }
+        self.ui(ctx);
+        if self.screenshot_requested {
+            frame.request_screenshot();
+        }
+        if std::mem::take(&mut self.close_requested) {
+            frame.close();
+        }
+    }
+}
+
+impl MyApp {
+    // 整个界面, 不需要 eframe::Frame, 可以直接用 egui::Context::run 驱动
+    pub fn ui(&mut self, ctx: &egui::Context) {
+        self.session_frame(ctx);
+        // 记录本帧的原始输入, 在任何控件消耗事件之前
+        ctx.input(|i| self.input_log.record(&i.raw, i.time));
+
+        // 在面板之前处理快捷键, 避免单行输入框自己处理 Ctrl+Z
+        let mut commands = self.shortcuts.poll(ctx, |_| self.commands_enabled());
+        // 多数键盘布局上 + 需要按 Shift, 而快捷键要求 Shift 完全一致
+        if let Some(zoom_in) = self.shortcuts.shortcut(AppCommand::ZoomIn) {
+            let shifted = KeyboardShortcut::new(zoom_in.modifiers | Modifiers::SHIFT, zoom_in.key);
+            if zoom_in.key == Key::PlusEquals
+                && !zoom_in.modifiers.shift
+                && self.commands_enabled()
+                && ctx.input_mut(|i| i.consume_shortcut(&shifted))
+            {
+                commands.push(AppCommand::ZoomIn);
+            }
+        }
+        for command in commands {
+            self.run_command(ctx, command);
+        }
+
+        if std::mem::take(&mut self.display_dirty) {
+            self.display.apply(
+                ctx,
+                &base_style(self.theme.visuals(self.system_theme)),
+                self.native_pixels_per_point,
+            );
+        }
+
+        let now = ctx.input(|i| i.time);
+        if self.show_history {
+            egui::SidePanel::right("history_panel")
+                .default_width(220.0)
+                .show(ctx, |ui| {
+                    ui.set_enabled(!self.dialogs.is_open());
+                    ui.heading(tr("history"));
+                    if let Some(index) = self.history.ui(ui, now) {
+                        self.jump_history(index);
+                    }
+                });
+        }
+
         // egui::CentralPanel 用于覆盖屏幕的剩余部分
         egui::CentralPanel::default().show(ctx, |ui| {
+            // 对话框打开时禁止与面板交互
+            ui.set_enabled(!self.dialogs.is_open());
             content(self, ui);
 
             // 展示选择的文件
-            if ui.button("Open file…").clicked() {
-                if let Some(path) = rfd::FileDialog::new().pick_file() {
-                    self.picked_path = Some(path.display().to_string());
-                }
+            if ui.button(tr("command-open-file")).clicked() {
+                self.open_file();
             }
             if let Some(picked_path) = &self.picked_path {
                 ui.horizontal(|ui| {
-                    ui.label("Picked file:");
+                    ui.label(tr("picked-file"));
                     ui.monospace(picked_path);
                 });
             }
@@ -207,7 +1138,7 @@ impl eframe::App for MyApp {
             // 展示拖拽的文件
             if !self.dropped_files.is_empty() {
                 ui.group(|ui| {
-                    ui.label("Dropped files:");
+                    ui.label(tr("dropped-files"));
 
                     for file in &self.dropped_files {
                         let mut info = if let Some(path) = &file.path {
@@ -220,10 +1151,10 @@ impl eframe::App for MyApp {
 
                         let mut additional_info = vec![];
                         if !file.name.is_empty() {
-                            additional_info.push(format!("type: {}", file.name));
+                            additional_info.push(tr_args("file-type", &[("type", &file.name)]));
                         }
                         if let Some(bytes) = &file.bytes {
-                            additional_info.push(format!("{} bytes", bytes.len()));
+                            additional_info.push(tr_plural("file-bytes", bytes.len() as i64, &[]));
                         }
                         if !additional_info.is_empty() {
                             info += &format!(" ({})", additional_info.join(", "));
@@ -234,25 +1165,6 @@ impl eframe::App for MyApp {
                 });
             }
 
-            // 键盘事件
-            // ScrollArea::vertical()
-            //     .auto_shrink([false; 2])
-            //     .stick_to_bottom(true)
-            //     .show(ui, |ui| {
-            //         ui.label(&self.text);
-            //     });
-
-            // if ctx.input(|i| i.key_pressed(Key::A)) {
-            //     self.text.push_str("\nPressed");
-            // }
-            // if ctx.input(|i| i.key_down(Key::A)) {
-            //     self.text.push_str("\nHeld");
-            //     ui.ctx().request_repaint(); // make sure we note the holding.
-            // }
-            // if ctx.input(|i| i.key_released(Key::A)) {
-            //     self.text.push_str("\nReleased");
-            // }
-
             // puffin
             // ui.separator();
             // let cmd = "cargo install puffin_viewer && puffin_viewer --url 127.0.0.1:8585";
@@ -271,6 +1183,11 @@ impl eframe::App for MyApp {
             // }
         });
 
+        // 输入法组字时先不记录, 上屏后再作为一步
+        if ime::preedit(ctx).is_none() {
+            self.record_history(now);
+        }
+
         preview_files_being_dropped(ctx);
         // 保存拖拽的文件
         ctx.input(|i| {
@@ -278,26 +1195,127 @@ impl eframe::App for MyApp {
                 self.dropped_files = i.raw.dropped_files.clone();
             }
         });
+        // 拖入的 Markdown 文件在文档窗口中打开
+        let dropped_markdown = self.dropped_files.iter().find_map(|file| {
+            let path = file.path.as_ref()?;
+            (Language::from_path(path) == Language::Markdown).then(|| path.clone())
+        });
+        if let Some(path) = dropped_markdown {
+            if self.markdown.path() != Some(path.as_path()) {
+                match self.markdown.open(&path) {
+                    Ok(()) => self.show_markdown = true,
+                    Err(err) => self.toasts.error(tr_args(
+                        "file-open-failed",
+                        &[("path", &path.display()), ("error", &err)],
+                    )),
+                }
+            }
+        }
 
-        // 确认对话框
-        if self.show_confirmation_dialog {
-            egui::Window::new("Do you want to quit?")
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    ui.horizontal(|ui| {
-                        if ui.button("Cancel").clicked() {
-                            self.show_confirmation_dialog = false;
-                        }
+        egui::Window::new(format!("📖 {}", tr("markdown-window")))
+            .id(egui::Id::new("markdown_window"))
+            .open(&mut self.show_markdown)
+            .default_size(egui::vec2(480.0, 600.0))
+            .show(ctx, |ui| self.markdown.ui(ui, &mut self.toasts));
 
-                        if ui.button("Yes!").clicked() {
-                            self.allowed_to_close = true;
-                            frame.close();
-                        }
-                    });
-                });
+        let mut show_input_log = self.show_input_log;
+        egui::Window::new(format!("🖱 {}", tr("input-window")))
+            .id(egui::Id::new("input_window"))
+            .open(&mut show_input_log)
+            .default_size(egui::vec2(520.0, 360.0))
+            .show(ctx, |ui| {
+                self.session_ui(ui);
+                ui.separator();
+                self.input_log.ui(ui, &mut self.toasts);
+            });
+        self.show_input_log = show_input_log;
+
+        egui::Window::new(format!("⌨ {}", tr("shortcuts-window")))
+            .id(egui::Id::new("shortcuts_window"))
+            .open(&mut self.show_shortcuts)
+            .resizable(false)
+            .show(ctx, |ui| self.shortcuts.ui(ui));
+        // 录制快捷键时关闭窗口, 取消录制
+        if !self.show_shortcuts {
+            self.shortcuts.cancel_recording();
+        }
+
+        egui::Window::new(format!("⚙ {}", tr("settings-window")))
+            .id(egui::Id::new("settings_window"))
+            .open(&mut self.show_settings)
+            .resizable(false)
+            .show(ctx, |ui| {
+                if self.display.ui(ui) {
+                    self.display_dirty = true;
+                }
+                ui.separator();
+                if self.theme.ui(ui, self.system_theme, &mut self.toasts) {
+                    self.display_dirty = true;
+                }
+            });
+
+        let mut analyze = false;
+        egui::Window::new(format!("⏳ {}", tr("tasks-window")))
+            .id(egui::Id::new("tasks_window"))
+            .open(&mut self.show_tasks)
+            .show(ctx, |ui| {
+                analyze = ui.button(tr("command-analyze-text")).clicked();
+                ui.separator();
+                self.tasks.ui(ui);
+            });
+        if analyze {
+            self.analyze_text(ctx);
+        }
+        self.poll_analysis();
+
+        if let Some(command) = self.palette.show(ctx, &self.shortcuts) {
+            self.run_command(ctx, command);
+        }
+
+        self.session_status(ctx);
+
+        self.dialogs.show(ctx);
+        self.toasts.show(ctx);
+
+        // 关闭确认对话框的结果
+        if let Some(id) = self.close_dialog {
+            if let Some(response) = self.dialogs.take_response(id) {
+                self.close_dialog = None;
+                match response {
+                    // 保存失败 (表单校验未通过) 时不退出
+                    DialogResponse::Button(0) if self.save_changes() => {
+                        self.allowed_to_close = true;
+                        self.close_requested = true;
+                    }
+                    DialogResponse::Button(1) => {
+                        self.allowed_to_close = true;
+                        self.close_requested = true;
+                    }
+                    _ => {}
+                }
+            }
         }
     }
 }
 
 pub const LOREM_IPSUM: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam, quis nostrud exercitation ullamco laboris nisi ut aliquip ex ea commodo consequat. Duis aute irure dolor in reprehenderit in voluptate velit esse cillum dolore eu fugiat nulla pariatur. Excepteur sint occaecat cupidatat non proident, sunt in culpa qui officia deserunt mollit anim id est laborum.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_control_is_accessible() {
+        let ctx = egui::Context::default();
+        let mut app = MyApp::with_context(&ctx);
+        let tree = AccessTree::capture(&ctx, |ctx| app.ui(ctx)).expect("no AccessKit tree");
+        for (role, key) in ACCESSIBLE_CONTROLS {
+            assert!(
+                tree.find(*role, tr(key)).is_some(),
+                "missing {role:?} {key}\n{}",
+                tree.dump()
+            );
+        }
+        assert_eq!(MyApp::check_accessibility(&tree), Vec::<String>::new());
+    }
+}
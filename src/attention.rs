@@ -0,0 +1,378 @@
+use std::time::{Duration, Instant};
+
+use egui::UserAttentionType;
+
+use crate::timer::{Clock, SystemClock, Timers};
+
+// 一次注意力请求的计划
+#[derive(Clone, Debug, PartialEq)]
+pub struct AttentionSchedule {
+    pub kind: UserAttentionType,
+    // 开始到第一次请求的时间
+    pub delay: Duration,
+    // 重复请求的间隔, None 表示只请求一次
+    pub repeat: Option<Duration>,
+    // 最多请求的次数, 包括第一次
+    pub max_requests: Option<u32>,
+    // 第一次请求后窗口一直没有焦点, 这么久之后升级为 Critical
+    pub escalate_after: Option<Duration>,
+    // 每次请求后自动重置
+    pub reset_after: Option<Duration>,
+}
+
+impl Default for AttentionSchedule {
+    fn default() -> Self {
+        Self {
+            kind: UserAttentionType::Informational,
+            delay: Duration::from_secs(2),
+            repeat: None,
+            max_requests: None,
+            escalate_after: None,
+            reset_after: None,
+        }
+    }
+}
+
+impl AttentionSchedule {
+    pub fn new(delay: Duration) -> Self {
+        Self {
+            delay,
+            ..Default::default()
+        }
+    }
+
+    pub fn kind(mut self, kind: UserAttentionType) -> Self {
+        self.kind = kind;
+        self
+    }
+
+    pub fn repeat_every(mut self, interval: Duration) -> Self {
+        self.repeat = Some(interval);
+        self
+    }
+
+    pub fn max_requests(mut self, max_requests: u32) -> Self {
+        self.max_requests = Some(max_requests);
+        self
+    }
+
+    pub fn escalate_after(mut self, duration: Duration) -> Self {
+        self.escalate_after = Some(duration);
+        self
+    }
+
+    pub fn reset_after(mut self, duration: Duration) -> Self {
+        self.reset_after = Some(duration);
+        self
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttentionState {
+    Idle,
+    // 等待第一次请求
+    Waiting,
+    // 已经请求过, 等待重复、升级或重置
+    Requested { requests: u32, escalated: bool },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Event {
+    Request,
+    Reset,
+}
+
+struct Active {
+    schedule: AttentionSchedule,
+    timers: Timers<Event>,
+    // 到期时窗口有焦点, 等失去焦点后再请求
+    deferred: bool,
+    first_request: Option<Instant>,
+    requests: u32,
+    escalated: bool,
+}
+
+impl Active {
+    fn kind(&self) -> UserAttentionType {
+        if self.escalated {
+            UserAttentionType::Critical
+        } else {
+            self.schedule.kind
+        }
+    }
+
+    fn escalate_at(&self) -> Option<Instant> {
+        if self.escalated || self.schedule.kind == UserAttentionType::Critical {
+            return None;
+        }
+        Some(self.first_request? + self.schedule.escalate_after?)
+    }
+
+    fn can_repeat(&self) -> bool {
+        self.schedule
+            .max_requests
+            .is_none_or(|max| self.requests < max)
+    }
+
+    fn is_finished(&self, focused: bool) -> bool {
+        self.timers.is_empty() && !self.deferred && (focused || self.escalate_at().is_none())
+    }
+}
+
+// 注意力请求的调度器. 每帧调用 set_focused 和 poll,
+// 把 poll 返回的请求交给 frame.request_user_attention
+pub struct AttentionScheduler<C: Clock = SystemClock> {
+    clock: C,
+    active: Option<Active>,
+    focused: bool,
+    // 取消时如果已经请求过, 需要重置一次
+    reset_pending: bool,
+}
+
+impl Default for AttentionScheduler {
+    fn default() -> Self {
+        Self::new(SystemClock)
+    }
+}
+
+impl<C: Clock> AttentionScheduler<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            active: None,
+            focused: true,
+            reset_pending: false,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    // 替换当前的计划
+    pub fn schedule(&mut self, schedule: AttentionSchedule) {
+        self.cancel();
+        let mut timers = Timers::default();
+        timers.schedule_in(&self.clock, Event::Request, schedule.delay);
+        self.active = Some(Active {
+            schedule,
+            timers,
+            deferred: false,
+            first_request: None,
+            requests: 0,
+            escalated: false,
+        });
+    }
+
+    pub fn cancel(&mut self) {
+        if let Some(active) = self.active.take() {
+            self.reset_pending |= active.requests > 0;
+        }
+    }
+
+    // 重新获得焦点时取消计划
+    pub fn set_focused(&mut self, focused: bool) {
+        let regained = focused && !self.focused;
+        self.focused = focused;
+        if regained
+            && self
+                .active
+                .as_ref()
+                .is_some_and(|active| active.requests > 0)
+        {
+            self.cancel();
+        }
+    }
+
+    pub fn state(&self) -> AttentionState {
+        match &self.active {
+            None => AttentionState::Idle,
+            Some(active) if active.requests == 0 => AttentionState::Waiting,
+            Some(active) => AttentionState::Requested {
+                requests: active.requests,
+                escalated: active.escalated,
+            },
+        }
+    }
+
+    // 下一次需要 poll 的时间, 用于 request_repaint_after.
+    // 等待失去焦点时返回 None, 焦点变化本身会触发重绘
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.reset_pending {
+            return Some(self.clock.now());
+        }
+        let active = self.active.as_ref()?;
+        if active.deferred && !self.focused {
+            return Some(self.clock.now());
+        }
+        let escalate_at = active.escalate_at().filter(|_| !self.focused);
+        [active.timers.next_deadline(), escalate_at]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    pub fn time_until_next(&self) -> Option<Duration> {
+        Some(
+            self.next_deadline()?
+                .saturating_duration_since(self.clock.now()),
+        )
+    }
+
+    // 返回现在需要发出的请求. 同时到期时请求优先于重置
+    pub fn poll(&mut self) -> Option<UserAttentionType> {
+        let now = self.clock.now();
+        let mut output = None;
+        if self.reset_pending {
+            self.reset_pending = false;
+            output = Some(UserAttentionType::Reset);
+        }
+        let focused = self.focused;
+        let Some(active) = &mut self.active else {
+            return output;
+        };
+
+        let due = active.timers.pop_due(now);
+        if due.contains(&Event::Reset) {
+            output = Some(UserAttentionType::Reset);
+        }
+        active.deferred |= due.contains(&Event::Request);
+        if !focused && active.escalate_at().is_some_and(|at| at <= now) {
+            active.escalated = true;
+            active.deferred = true;
+        }
+        // 有焦点时推迟到失去焦点
+        if !focused && active.deferred {
+            active.deferred = false;
+            active.requests += 1;
+            active.first_request.get_or_insert(now);
+            if let Some(after) = active.schedule.reset_after {
+                active.timers.schedule_at(Event::Reset, now + after);
+            }
+            active.timers.cancel(&Event::Request);
+            if let Some(interval) = active.schedule.repeat.filter(|_| active.can_repeat()) {
+                active.timers.schedule_at(Event::Request, now + interval);
+            }
+            output = Some(active.kind());
+        }
+
+        if active.is_finished(focused) {
+            self.active = None;
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::UserAttentionType::{Critical, Informational, Reset};
+
+    use super::*;
+    use crate::timer::FakeClock;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn unfocused(schedule: AttentionSchedule) -> (FakeClock, AttentionScheduler<FakeClock>) {
+        let clock = FakeClock::default();
+        let mut scheduler = AttentionScheduler::new(clock.clone());
+        scheduler.set_focused(false);
+        scheduler.schedule(schedule);
+        (clock, scheduler)
+    }
+
+    #[test]
+    fn delay_then_repeat_up_to_max_requests() {
+        let (clock, mut scheduler) = unfocused(
+            AttentionSchedule::new(secs(2))
+                .repeat_every(secs(3))
+                .max_requests(3),
+        );
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), None);
+        assert_eq!(scheduler.time_until_next(), Some(secs(1)));
+        for _ in 0..3 {
+            clock.advance(secs(1));
+            assert_eq!(scheduler.poll(), Some(Informational));
+            clock.advance(secs(2));
+            assert_eq!(scheduler.poll(), None);
+        }
+        assert_eq!(scheduler.state(), AttentionState::Idle);
+        assert_eq!(scheduler.next_deadline(), None);
+    }
+
+    #[test]
+    fn deferred_while_focused() {
+        let clock = FakeClock::default();
+        let mut scheduler = AttentionScheduler::new(clock.clone());
+        scheduler.schedule(AttentionSchedule::new(secs(1)));
+        clock.advance(secs(5));
+        assert_eq!(scheduler.poll(), None);
+        assert_eq!(scheduler.state(), AttentionState::Waiting);
+        // 等待失去焦点时不需要定时重绘
+        assert_eq!(scheduler.next_deadline(), None);
+        scheduler.set_focused(false);
+        assert_eq!(scheduler.next_deadline(), Some(clock.now()));
+        assert_eq!(scheduler.poll(), Some(Informational));
+        assert_eq!(scheduler.state(), AttentionState::Idle);
+    }
+
+    #[test]
+    fn escalates_to_critical_while_unfocused() {
+        let (clock, mut scheduler) = unfocused(
+            AttentionSchedule::new(secs(1))
+                .repeat_every(secs(10))
+                .escalate_after(secs(5)),
+        );
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), Some(Informational));
+        assert_eq!(scheduler.time_until_next(), Some(secs(5)));
+        clock.advance(secs(4));
+        assert_eq!(scheduler.poll(), None);
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), Some(Critical));
+        assert_eq!(
+            scheduler.state(),
+            AttentionState::Requested {
+                requests: 2,
+                escalated: true
+            }
+        );
+        // 升级后的重复请求也是 Critical
+        clock.advance(secs(10));
+        assert_eq!(scheduler.poll(), Some(Critical));
+    }
+
+    #[test]
+    fn refocus_cancels_with_a_single_reset() {
+        let (clock, mut scheduler) =
+            unfocused(AttentionSchedule::new(secs(1)).repeat_every(secs(1)));
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), Some(Informational));
+        scheduler.set_focused(true);
+        assert_eq!(scheduler.state(), AttentionState::Idle);
+        assert_eq!(scheduler.poll(), Some(Reset));
+        clock.advance(secs(5));
+        assert_eq!(scheduler.poll(), None);
+        assert_eq!(scheduler.next_deadline(), None);
+    }
+
+    #[test]
+    fn request_wins_over_reset_when_both_are_due() {
+        let (clock, mut scheduler) = unfocused(
+            AttentionSchedule::new(secs(2))
+                .repeat_every(secs(1))
+                .max_requests(2)
+                .reset_after(secs(1)),
+        );
+        clock.advance(secs(2));
+        assert_eq!(scheduler.poll(), Some(Informational));
+        // 重复请求和重置同时到期
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), Some(Informational));
+        clock.advance(secs(1));
+        assert_eq!(scheduler.poll(), Some(Reset));
+        assert_eq!(scheduler.state(), AttentionState::Idle);
+    }
+}
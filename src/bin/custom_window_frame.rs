@@ -91,6 +91,8 @@ fn title_bar_ui(
     let painter = ui.painter();
 
     let title_bar_response = ui.interact(title_bar_rect, Id::new("title_bar"), Sense::click());
+    // 标题是直接画出来的, 读屏软件需要从这里读到窗口标题
+    title_bar_response.widget_info(|| WidgetInfo::labeled(WidgetType::Label, title));
 
     // Paint the title:
     painter.text(
@@ -129,13 +131,16 @@ fn title_bar_ui(
 
 /// Show some close/maximize/minimize buttons for the native window.
 fn close_maximize_minimize(ui: &mut egui::Ui, frame: &mut eframe::Frame) {
-    use egui::{Button, RichText};
+    use egui::{Button, RichText, WidgetInfo, WidgetType};
 
     let button_height = 12.0;
+    // 图标按钮的名称, 读屏软件朗读它而不是图标字符
+    let name = |text: &'static str| move || WidgetInfo::labeled(WidgetType::Button, text);
 
     let close_response = ui
         .add(Button::new(RichText::new("❌").size(button_height)))
         .on_hover_text("Close the window");
+    close_response.widget_info(name("Close the window"));
     if close_response.clicked() {
         frame.close();
     }
@@ -144,6 +149,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui, frame: &mut eframe::Frame) {
         let maximized_response = ui
             .add(Button::new(RichText::new("🗗").size(button_height)))
             .on_hover_text("Restore window");
+        maximized_response.widget_info(name("Restore window"));
         if maximized_response.clicked() {
             frame.set_maximized(false);
         }
@@ -151,6 +157,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui, frame: &mut eframe::Frame) {
         let maximized_response = ui
             .add(Button::new(RichText::new("🗗").size(button_height)))
             .on_hover_text("Maximize window");
+        maximized_response.widget_info(name("Maximize window"));
         if maximized_response.clicked() {
             frame.set_maximized(true);
         }
@@ -159,6 +166,7 @@ fn close_maximize_minimize(ui: &mut egui::Ui, frame: &mut eframe::Frame) {
     let minimized_response = ui
         .add(Button::new(RichText::new("🗕").size(button_height)))
         .on_hover_text("Minimize the window");
+    minimized_response.widget_info(name("Minimize the window"));
     if minimized_response.clicked() {
         frame.set_minimized(true);
     }
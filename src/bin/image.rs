@@ -57,30 +57,36 @@ impl eframe::App for ImageApp {
             );
 
             ui.horizontal(|ui| {
-                ui.label("Tint:");
+                let tint_label = ui.label("Tint:");
                 egui::color_picker::color_edit_button_srgba(
                     ui,
                     tint,
                     egui::color_picker::Alpha::BlendOrAdditive,
-                );
+                )
+                .labelled_by(tint_label.id);
 
                 ui.add_space(16.0);
 
-                ui.label("Rounding:");
+                let rounding_label = ui.label("Rounding:");
                 ui.add(
                     egui::DragValue::new(rounding)
                         .speed(1.0)
                         .clamp_range(0.0..=0.5 * image.size_vec2().min_elem()),
-                );
+                )
+                .labelled_by(rounding_label.id);
             });
 
             ui.add_space(32.0);
 
             ui.heading("This is an image you can click:");
-            ui.add(egui::ImageButton::new(
+            // 图片按钮没有文字, 需要单独设置读屏软件朗读的名称
+            let response = ui.add(egui::ImageButton::new(
                 image.texture_id(ctx),
                 image.size_vec2(),
             ));
+            response.widget_info(|| {
+                egui::WidgetInfo::labeled(egui::WidgetType::ImageButton, "Clickable image")
+            });
         });
     }
 }
@@ -1,64 +1,408 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
 use eframe::egui;
+use serde::Deserialize;
+use serde_json::Value;
+
+// 默认的向导配置, 可以用 --config <path> 替换
+const DEFAULT_CONFIG: &str = include_str!("../../assets/wizard.json");
 
-// eframe::run_native 会阻塞后续窗口的创建
+// 按配置依次打开一系列原生窗口, 每个步骤一个窗口.
+// eframe::run_native 会阻塞后续窗口的创建, 上一个窗口关闭后才打开下一个
 fn main() -> Result<(), eframe::Error> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
 
-    if cfg!(target_os = "macos") {
-        eprintln!("WARNING: this example does not work on Mac! See https://github.com/emilk/egui/issues/1918");
+    let mut args = std::env::args().skip(1);
+    let mut config_path = None;
+    let mut output = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--config" => config_path = args.next().map(PathBuf::from),
+            "--output" => output = args.next().map(PathBuf::from),
+            _ => eprintln!("Unknown argument: {arg}"),
+        }
     }
-
-    let options = eframe::NativeOptions {
-        run_and_return: true,
-        initial_window_size: Some(egui::vec2(320.0, 240.0)),
-        ..Default::default()
+    let config = match WizardConfig::load(config_path) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("Failed to load wizard config: {err}");
+            std::process::exit(1);
+        }
     };
 
-    eprintln!("Starting first window…");
-    eframe::run_native(
-        "First Window",
-        options.clone(),
-        Box::new(|_cc| Box::new(MyApp { has_next: true })),
-    )?;
-
-    std::thread::sleep(std::time::Duration::from_secs(2));
-
-    eprintln!("Starting second window…");
-    eframe::run_native(
-        "Second Window",
-        options.clone(),
-        Box::new(|_cc| Box::new(MyApp { has_next: true })),
-    )?;
-
-    std::thread::sleep(std::time::Duration::from_secs(2));
-
-    eprintln!("Starting third window…");
-    eframe::run_native(
-        "Third Window",
-        options,
-        Box::new(|_cc| Box::new(MyApp { has_next: false })),
-    )
+    Wizard::new(config, output).run()
+}
+
+// 切换到下一个窗口时的过渡
+#[derive(Clone, Copy, Debug, Default, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Transition {
+    #[default]
+    Immediate,
+    // 关闭当前窗口后等待一段时间再打开下一个窗口
+    Delay {
+        millis: u64,
+    },
+    // 新窗口从背景色淡入
+    Fade {
+        millis: u64,
+    },
+}
+
+impl Transition {
+    fn delay(self) -> Duration {
+        match self {
+            Transition::Delay { millis } => Duration::from_millis(millis),
+            Transition::Immediate | Transition::Fade { .. } => Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum FieldKind {
+    Text {
+        #[serde(default)]
+        multiline: bool,
+    },
+    Checkbox,
+    Choice {
+        options: Vec<String>,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Field {
+    key: String,
+    label: String,
+    #[serde(flatten)]
+    kind: FieldKind,
+    #[serde(default)]
+    required: bool,
+    default: Option<Value>,
+}
+
+impl Field {
+    fn initial_value(&self) -> Value {
+        if let Some(default) = &self.default {
+            return default.clone();
+        }
+        match &self.kind {
+            FieldKind::Text { .. } => Value::String(String::new()),
+            FieldKind::Checkbox => Value::Bool(false),
+            FieldKind::Choice { options } => options
+                .first()
+                .map_or(Value::Null, |option| Value::String(option.clone())),
+        }
+    }
+
+    fn is_filled(&self, value: Option<&Value>) -> bool {
+        match value {
+            None | Some(Value::Null) => false,
+            Some(Value::String(text)) => !text.trim().is_empty(),
+            // 必填的复选框需要勾选, 例如同意协议
+            Some(Value::Bool(checked)) => *checked,
+            Some(_) => true,
+        }
+    }
+
+    fn ui(&self, ui: &mut egui::Ui, value: &mut Value) {
+        match &self.kind {
+            FieldKind::Text { multiline } => {
+                let label = ui.label(&self.label);
+                let mut text = value.as_str().unwrap_or_default().to_owned();
+                let response = if *multiline {
+                    ui.text_edit_multiline(&mut text)
+                } else {
+                    ui.text_edit_singleline(&mut text)
+                };
+                if response.labelled_by(label.id).changed() {
+                    *value = Value::String(text);
+                }
+            }
+            FieldKind::Checkbox => {
+                let mut checked = value.as_bool().unwrap_or_default();
+                if ui.checkbox(&mut checked, &self.label).changed() {
+                    *value = Value::Bool(checked);
+                }
+            }
+            FieldKind::Choice { options } => {
+                let selected = value.as_str().unwrap_or_default().to_owned();
+                egui::ComboBox::new(&self.key, &self.label)
+                    .selected_text(&selected)
+                    .show_ui(ui, |ui| {
+                        for option in options {
+                            if ui.selectable_label(*option == selected, option).clicked() {
+                                *value = Value::String(option.clone());
+                            }
+                        }
+                    });
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Step {
+    title: String,
+    // {key} 会被替换为之前步骤的结果
+    #[serde(default)]
+    prompt: String,
+    #[serde(default)]
+    fields: Vec<Field>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct WizardConfig {
+    title: String,
+    #[serde(default)]
+    transition: Transition,
+    steps: Vec<Step>,
+}
+
+impl WizardConfig {
+    fn load(path: Option<PathBuf>) -> Result<Self, String> {
+        let text = match &path {
+            Some(path) => std::fs::read_to_string(path).map_err(|err| err.to_string())?,
+            None => DEFAULT_CONFIG.to_owned(),
+        };
+        let config: Self = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+        if config.steps.is_empty() {
+            return Err("the wizard has no steps".to_owned());
+        }
+        Ok(config)
+    }
+}
+
+fn fill_template(template: &str, results: &BTreeMap<String, Value>) -> String {
+    let mut text = template.to_owned();
+    for (key, value) in results {
+        let value = match value {
+            Value::String(value) => value.clone(),
+            value => value.to_string(),
+        };
+        text = text.replace(&format!("{{{key}}}"), &value);
+    }
+    text
+}
+
+// 所有窗口共享的向导状态
+struct Wizard {
+    config: WizardConfig,
+    step: usize,
+    // 所有步骤的结果, 返回上一步时保留
+    results: BTreeMap<String, Value>,
+    // 当前窗口关闭后打开的步骤, None 表示结束
+    next: Option<usize>,
+    output: Option<PathBuf>,
 }
 
-struct MyApp {
-    pub(crate) has_next: bool,
+impl Wizard {
+    fn new(config: WizardConfig, output: Option<PathBuf>) -> Self {
+        let mut results = BTreeMap::new();
+        for field in config.steps.iter().flat_map(|step| &step.fields) {
+            results
+                .entry(field.key.clone())
+                .or_insert_with(|| field.initial_value());
+        }
+        Self {
+            config,
+            step: 0,
+            results,
+            next: None,
+            output,
+        }
+    }
+
+    // 每个步骤打开一个原生窗口, 直接关闭窗口时退出向导.
+    // Mac 上 run_and_return 关闭窗口后不会返回 (https://github.com/emilk/egui/issues/1918),
+    // 所以所有步骤共用一个原生窗口, 切换步骤时更新标题
+    fn run(self) -> Result<(), eframe::Error> {
+        let separate_windows = !cfg!(target_os = "macos");
+        let options = eframe::NativeOptions {
+            run_and_return: separate_windows,
+            initial_window_size: Some(egui::vec2(420.0, 320.0)),
+            ..Default::default()
+        };
+        let delay = self.config.transition.delay();
+        let wizard = Rc::new(RefCell::new(self));
+        // 第一个窗口不需要等待
+        let mut waiting = Duration::ZERO;
+        loop {
+            let title = wizard.borrow().window_title();
+            eprintln!("Opening {title}…");
+            let window = StepWindow {
+                wizard: wizard.clone(),
+                entered: Instant::now(),
+                delay: waiting,
+                separate_windows,
+            };
+            eframe::run_native(&title, options.clone(), Box::new(|_cc| Box::new(window)))?;
+
+            let mut wizard = wizard.borrow_mut();
+            let Some(step) = wizard.next.take() else {
+                return Ok(());
+            };
+            wizard.step = step;
+            waiting = delay;
+        }
+    }
+
+    fn window_title(&self) -> String {
+        format!("{} - {}", self.config.title, self.current().title)
+    }
+
+    fn current(&self) -> &Step {
+        &self.config.steps[self.step]
+    }
+
+    fn is_last(&self) -> bool {
+        self.step + 1 == self.config.steps.len()
+    }
+
+    fn can_continue(&self) -> bool {
+        self.current()
+            .fields
+            .iter()
+            .filter(|field| field.required)
+            .all(|field| field.is_filled(self.results.get(&field.key)))
+    }
+
+    fn finish(&self) {
+        let json = serde_json::to_string_pretty(&self.results).unwrap_or_default();
+        match &self.output {
+            Some(path) => {
+                if let Err(err) = std::fs::write(path, json) {
+                    eprintln!("Failed to write {}: {err}", path.display());
+                }
+            }
+            None => println!("{json}"),
+        }
+    }
+
+    // 返回是否关闭当前窗口
+    fn step_ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let step = self.current().clone();
+        ui.label(format!(
+            "Step {} of {}",
+            self.step + 1,
+            self.config.steps.len()
+        ));
+        ui.heading(&step.title);
+        ui.label(fill_template(&step.prompt, &self.results));
+        ui.add_space(8.0);
+        for field in &step.fields {
+            let value = self
+                .results
+                .entry(field.key.clone())
+                .or_insert_with(|| field.initial_value());
+            field.ui(ui, value);
+        }
+
+        ui.add_space(8.0);
+        let mut close = false;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(self.step > 0, egui::Button::new("Back"))
+                .clicked()
+            {
+                self.next = Some(self.step - 1);
+                close = true;
+            }
+            let can_continue = self.can_continue();
+            let next = if self.is_last() { "Finish" } else { "Next" };
+            if ui
+                .add_enabled(can_continue, egui::Button::new(next))
+                .clicked()
+            {
+                if self.is_last() {
+                    self.finish();
+                } else {
+                    self.next = Some(self.step + 1);
+                }
+                close = true;
+            }
+            if !can_continue {
+                ui.weak("Fill in the required fields");
+            }
+        });
+        close
+    }
+}
+
+// 一个步骤的原生窗口
+struct StepWindow {
+    wizard: Rc<RefCell<Wizard>>,
+    // 当前步骤开始显示的时间, 用于延迟和淡入
+    entered: Instant,
+    // 显示步骤内容之前的等待时间
+    delay: Duration,
+    // false 时在同一个窗口中切换步骤
+    separate_windows: bool,
 }
 
-impl eframe::App for MyApp {
+impl StepWindow {
+    fn next_step(&mut self, frame: &mut eframe::Frame) {
+        let mut wizard = self.wizard.borrow_mut();
+        match wizard.next {
+            Some(step) if !self.separate_windows => {
+                wizard.next = None;
+                wizard.step = step;
+                frame.set_window_title(&wizard.window_title());
+                self.entered = Instant::now();
+                self.delay = wizard.config.transition.delay();
+            }
+            _ => frame.close(),
+        }
+    }
+}
+
+impl eframe::App for StepWindow {
     fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let label_text = if self.has_next {
-                "When this window is closed the next will be opened after a short delay"
+        let transition = self.wizard.borrow().config.transition;
+        // 延迟: 窗口已经打开并响应输入, 到时间后再显示步骤内容
+        let waiting = self.delay.saturating_sub(self.entered.elapsed());
+
+        let mut close = false;
+        let panel = egui::CentralPanel::default().show(ctx, |ui| {
+            let mut wizard = self.wizard.borrow_mut();
+            if waiting.is_zero() {
+                close = wizard.step_ui(ui);
             } else {
-                "This is the last window. Program will end when closed"
-            };
-            ui.label(label_text);
-            if ui.button("Close").clicked() {
-                eprintln!("Pressed Close button");
-                frame.close();
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label(format!("Opening {}…", wizard.current().title));
+                });
+                ctx.request_repaint_after(waiting);
             }
         });
+        if close {
+            self.next_step(frame);
+        }
+
+        // 淡入: 在新窗口上覆盖逐渐透明的背景色
+        if let Transition::Fade { millis } = transition {
+            let duration = Duration::from_millis(millis).as_secs_f32();
+            let t = self.entered.elapsed().as_secs_f32() / duration.max(f32::EPSILON);
+            if t < 1.0 {
+                let alpha = 1.0 - t;
+                ctx.layer_painter(egui::LayerId::new(
+                    egui::Order::Foreground,
+                    egui::Id::new("wizard fade"),
+                ))
+                .rect_filled(
+                    panel.response.rect,
+                    0.0,
+                    ctx.style().visuals.panel_fill.gamma_multiply(alpha),
+                );
+                ctx.request_repaint();
+            }
+        }
     }
 }
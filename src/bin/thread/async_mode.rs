@@ -0,0 +1,273 @@
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::oneshot::error::TryRecvError;
+use tokio::sync::{oneshot, watch};
+use tokio::task::JoinHandle;
+
+// 回显服务器模拟的网络延迟
+const ECHO_DELAY: Duration = Duration::from_millis(300);
+const TIMER_INTERVAL: Duration = Duration::from_secs(1);
+// accept 失败后 (例如文件描述符用尽) 等待一段时间再重试
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+// 异步任务和 egui 事件循环之间的桥: future 完成时唤醒界面重绘
+struct Bridge {
+    runtime: Runtime,
+    ctx: egui::Context,
+}
+
+impl Bridge {
+    fn new(ctx: &egui::Context) -> io::Result<Self> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .thread_name("tokio-panel")
+            .enable_all()
+            .build()?;
+        Ok(Self {
+            runtime,
+            ctx: ctx.clone(),
+        })
+    }
+
+    // 结果通过 Promise 在 update 中读取
+    fn spawn<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = T> + Send + 'static,
+    ) -> Promise<T> {
+        let (tx, rx) = oneshot::channel();
+        let ctx = self.ctx.clone();
+        let task = self.runtime.spawn(async move {
+            let _ = tx.send(future.await);
+            ctx.request_repaint();
+        });
+        Promise {
+            rx,
+            task,
+            value: None,
+        }
+    }
+
+    // 持续运行的任务, 自己决定何时唤醒界面
+    fn spawn_task<F>(&self, task: impl FnOnce(egui::Context) -> F) -> JoinHandle<()>
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.runtime.spawn(task(self.ctx.clone()))
+    }
+}
+
+// 异步任务的结果. 丢弃时取消任务
+struct Promise<T> {
+    rx: oneshot::Receiver<T>,
+    task: JoinHandle<()>,
+    // future panic 时发送端被丢弃, 结果为 Err
+    value: Option<Result<T, String>>,
+}
+
+impl<T> Promise<T> {
+    fn ready(&mut self) -> Option<&Result<T, String>> {
+        if self.value.is_none() {
+            self.value = match self.rx.try_recv() {
+                Ok(value) => Some(Ok(value)),
+                Err(TryRecvError::Empty) => None,
+                Err(TryRecvError::Closed) => Some(Err("the task panicked".to_owned())),
+            };
+        }
+        self.value.as_ref()
+    }
+}
+
+impl<T> Drop for Promise<T> {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// 本地回显服务器, 代替真实的网络服务
+async fn echo_server(listener: TcpListener) {
+    loop {
+        let socket = match listener.accept().await {
+            Ok((socket, _)) => socket,
+            Err(err) => {
+                log::warn!("Echo server failed to accept a connection: {err}");
+                tokio::time::sleep(ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let (reader, mut writer) = socket.into_split();
+            let mut lines = BufReader::new(reader).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                tokio::time::sleep(ECHO_DELAY).await;
+                if writer
+                    .write_all(format!("{line}\n").as_bytes())
+                    .await
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+// 发送一行并等待回显, 返回回显内容和往返时间
+async fn echo(addr: SocketAddr, message: String) -> io::Result<(String, Duration)> {
+    let start = Instant::now();
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.write_all(format!("{message}\n").as_bytes()).await?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).await?;
+    Ok((line.trim_end().to_owned(), start.elapsed()))
+}
+
+// 由异步任务驱动的面板: 定时器计数和回显请求
+struct AsyncPanel {
+    panel_nr: usize,
+    ticks: watch::Receiver<u64>,
+    timer: JoinHandle<()>,
+    message: String,
+    echo: Option<Promise<io::Result<(String, Duration)>>>,
+}
+
+impl AsyncPanel {
+    fn new(panel_nr: usize, bridge: &Bridge) -> Self {
+        let (tx, ticks) = watch::channel(0);
+        let timer = bridge.spawn_task(|ctx| async move {
+            let mut interval = tokio::time::interval(TIMER_INTERVAL);
+            let mut count = 0;
+            loop {
+                interval.tick().await;
+                if tx.send(count).is_err() {
+                    break;
+                }
+                count += 1;
+                ctx.request_repaint();
+            }
+        });
+        Self {
+            panel_nr,
+            ticks,
+            timer,
+            message: format!("Hello from panel {panel_nr}"),
+            echo: None,
+        }
+    }
+
+    fn show(&mut self, ctx: &egui::Context, bridge: &Bridge, addr: SocketAddr) {
+        let pos = egui::pos2(560.0, 128.0 * (self.panel_nr as f32 + 1.0));
+        egui::Window::new(format!("Async panel {}", self.panel_nr))
+            .default_pos(pos)
+            .show(ctx, |ui| {
+                ui.label(format!("Timer ticks: {}", *self.ticks.borrow()));
+                ui.horizontal(|ui| {
+                    let label = ui.label("Message: ");
+                    ui.text_edit_singleline(&mut self.message)
+                        .labelled_by(label.id);
+                });
+                let waiting = self
+                    .echo
+                    .as_mut()
+                    .is_some_and(|echo| echo.ready().is_none());
+                if ui
+                    .add_enabled(!waiting, egui::Button::new("Send to echo server"))
+                    .clicked()
+                {
+                    self.echo = Some(bridge.spawn(echo(addr, self.message.clone())));
+                }
+                match self.echo.as_mut().map(Promise::ready) {
+                    None => {}
+                    Some(None) => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Waiting for echo…");
+                        });
+                    }
+                    Some(Some(Ok(Ok((reply, elapsed))))) => {
+                        ui.label(format!(
+                            "Echo: {reply} ({:.0} ms)",
+                            elapsed.as_secs_f64() * 1000.0
+                        ));
+                    }
+                    Some(Some(Ok(Err(err)))) => {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Echo failed: {err}"),
+                        );
+                    }
+                    Some(Some(Err(err))) => {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Echo task failed: {err}"),
+                        );
+                    }
+                }
+            });
+    }
+}
+
+impl Drop for AsyncPanel {
+    fn drop(&mut self) {
+        self.timer.abort();
+    }
+}
+
+// 异步模式: 一个 Tokio 运行时、本地回显服务器和若干面板
+pub struct AsyncDemo {
+    panels: Vec<AsyncPanel>,
+    server: JoinHandle<()>,
+    addr: SocketAddr,
+    bridge: Bridge,
+}
+
+impl AsyncDemo {
+    pub fn new(ctx: &egui::Context) -> io::Result<Self> {
+        let bridge = Bridge::new(ctx)?;
+        let listener = bridge
+            .runtime
+            .block_on(TcpListener::bind(("127.0.0.1", 0)))?;
+        let addr = listener.local_addr()?;
+        let server = bridge.runtime.spawn(echo_server(listener));
+        let mut demo = Self {
+            panels: Vec::new(),
+            server,
+            addr,
+            bridge,
+        };
+        demo.add_panel();
+        demo.add_panel();
+        Ok(demo)
+    }
+
+    fn add_panel(&mut self) {
+        let panel = AsyncPanel::new(self.panels.len(), &self.bridge);
+        self.panels.push(panel);
+    }
+
+    // 主窗口中的异步模式信息
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.label(format!("Echo server listening on {}", self.addr));
+        if ui.button("Add async panel").clicked() {
+            self.add_panel();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        for panel in &mut self.panels {
+            panel.show(ctx, &self.bridge, self.addr);
+        }
+    }
+}
+
+impl Drop for AsyncDemo {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
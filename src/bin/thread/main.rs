@@ -0,0 +1,739 @@
+#![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use eframe::egui;
+use egui_demo::panic_message;
+
+#[cfg(feature = "async")]
+mod async_mode;
+
+// 线程空闲时的心跳间隔
+const HEARTBEAT: Duration = Duration::from_millis(100);
+// 超过该时间没有心跳的线程视为卡住
+const STALL_TIMEOUT: Duration = Duration::from_secs(1);
+// 模拟一次耗时计算
+const SLOW_WORK: Duration = Duration::from_secs(3);
+// 关闭时等待线程退出的最长时间
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(2);
+// 修改记录保留的条数
+const CHANGE_LOG_LEN: usize = 200;
+// 统计最近多少帧的耗时
+const LATENCY_FRAMES: usize = 120;
+
+fn main() -> Result<(), eframe::Error> {
+    env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
+    let options = eframe::NativeOptions {
+        initial_window_size: Some(egui::vec2(1024.0, 768.0)),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "My parallel egui App",
+        options,
+        Box::new(|cc| Box::new(ThreadApp::new(&cc.egui_ctx))),
+    )
+}
+
+// 界面发给线程的修改
+enum Command {
+    SetName(String),
+    SetAge(u32),
+    // 阻塞线程一段时间, 用于演示卡住检测
+    SlowWork(Duration),
+    // 用于演示 panic 的捕获
+    Panic,
+    // 暂停时修改先排队, 继续后再处理
+    Pause,
+    Resume,
+    // 共享存储也持有发送端, 不能靠关闭通道让线程退出
+    Stop,
+}
+
+// 线程收到的消息: 本窗口的修改, 或共享存储广播的变更
+enum Message {
+    Command(u64, Command),
+    Changed(Change),
+}
+
+impl From<Change> for Message {
+    fn from(change: Change) -> Self {
+        Self::Changed(change)
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Field {
+    Name(String),
+    Age(u32),
+}
+
+impl std::fmt::Display for Field {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "name = {name:?}"),
+            Self::Age(age) => write!(f, "age = {age}"),
+        }
+    }
+}
+
+// 共享存储中的一次修改, seq 全局递增
+#[derive(Clone, Debug)]
+struct Change {
+    seq: u64,
+    author: usize,
+    field: Field,
+    at: Instant,
+}
+
+// 所有窗口共享的字段. 每个字段记录最后一次修改的 seq,
+// 只接受更新的修改, 收到的顺序不同也会得到相同的结果
+#[derive(Clone, Debug)]
+struct Shared {
+    name: String,
+    age: u32,
+    name_seq: u64,
+    age_seq: u64,
+}
+
+impl Default for Shared {
+    fn default() -> Self {
+        Self {
+            name: "Arthur".into(),
+            age: 12,
+            name_seq: 0,
+            age_seq: 0,
+        }
+    }
+}
+
+impl Shared {
+    fn apply(&mut self, change: &Change) {
+        match &change.field {
+            Field::Name(name) if change.seq > self.name_seq => {
+                self.name = name.clone();
+                self.name_seq = change.seq;
+            }
+            Field::Age(age) if change.seq > self.age_seq => {
+                self.age = *age;
+                self.age_seq = change.seq;
+            }
+            _ => {}
+        }
+    }
+}
+
+type Subscriber = Box<dyn Fn(&Change) -> bool + Send + Sync>;
+
+struct StoreInner {
+    state: Shared,
+    seq: u64,
+    // 最近的修改, 用于显示修改记录
+    log: VecDeque<Change>,
+    subscribers: Vec<Subscriber>,
+}
+
+// 可订阅的共享存储, 所有修改在写锁内排序并广播给订阅者
+struct Store(RwLock<StoreInner>);
+
+impl Store {
+    fn new(state: Shared) -> Self {
+        Self(RwLock::new(StoreInner {
+            state,
+            seq: 0,
+            log: VecDeque::with_capacity(CHANGE_LOG_LEN),
+            subscribers: Vec::new(),
+        }))
+    }
+
+    // 返回当前状态, 之后的修改都会发送到 tx, 不会遗漏也不会重复.
+    // 接收端关闭后自动取消订阅
+    fn subscribe<M: From<Change> + Send + 'static>(&self, tx: mpsc::Sender<M>) -> Shared {
+        let mut inner = self.0.write().expect("store poisoned");
+        inner.subscribers.push(Box::new(move |change| {
+            tx.send(change.clone().into()).is_ok()
+        }));
+        inner.state.clone()
+    }
+
+    fn publish(&self, author: usize, field: Field) -> Change {
+        let mut inner = self.0.write().expect("store poisoned");
+        inner.seq += 1;
+        let change = Change {
+            seq: inner.seq,
+            author,
+            field,
+            at: Instant::now(),
+        };
+        inner.state.apply(&change);
+        if inner.log.len() == CHANGE_LOG_LEN {
+            inner.log.pop_front();
+        }
+        inner.log.push_back(change.clone());
+        inner.subscribers.retain(|subscriber| subscriber(&change));
+        change
+    }
+
+    fn log(&self) -> Vec<Change> {
+        self.0
+            .read()
+            .expect("store poisoned")
+            .log
+            .iter()
+            .cloned()
+            .collect()
+    }
+}
+
+// 线程计算后发布的状态, 界面只读取快照, 不等待线程
+#[derive(Clone)]
+struct Snapshot {
+    // 已处理的最后一个修改
+    revision: u64,
+    name: String,
+    age: u32,
+    greeting: String,
+}
+
+struct ThreadState {
+    thread_nr: usize,
+    revision: u64,
+    shared: Shared,
+}
+
+impl ThreadState {
+    fn handle(&mut self, message: Message, store: &Store) {
+        match message {
+            Message::Command(revision, command) => {
+                self.revision = revision;
+                match command {
+                    // 自己的修改立即生效, 稍后收到的广播会被忽略
+                    Command::SetName(name) => {
+                        let change = store.publish(self.thread_nr, Field::Name(name));
+                        self.shared.apply(&change);
+                    }
+                    Command::SetAge(age) => {
+                        let change = store.publish(self.thread_nr, Field::Age(age));
+                        self.shared.apply(&change);
+                    }
+                    Command::SlowWork(duration) => std::thread::sleep(duration),
+                    Command::Panic => panic!("panic requested from the UI"),
+                    Command::Pause | Command::Resume | Command::Stop => {}
+                }
+            }
+            Message::Changed(change) => self.shared.apply(&change),
+        }
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let Shared { name, age, .. } = &self.shared;
+        Snapshot {
+            revision: self.revision,
+            name: name.clone(),
+            age: *age,
+            greeting: format!("Hello '{name}', age {age}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Status {
+    Running,
+    Paused,
+    // 已通知线程退出, 等待线程处理完当前修改
+    Stopping,
+    Stopped,
+    Panicked(String),
+}
+
+// 界面线程持有的线程句柄和最新快照
+struct Worker {
+    thread_nr: usize,
+    // 线程退出并 join 后为 None
+    handle: Option<JoinHandle<()>>,
+    // 已通知线程退出时为 None
+    commands: Option<mpsc::Sender<Message>>,
+    snapshots: mpsc::Receiver<Snapshot>,
+    // 最后一次心跳, 相对 ThreadApp::epoch 的毫秒数
+    heartbeat: Arc<AtomicU64>,
+    status: Status,
+    started: Instant,
+    stopped: Option<Instant>,
+    // 线程退出后重新启动
+    restart_requested: bool,
+    // 界面上显示和编辑的值, 线程处理完所有修改后才用快照覆盖, 避免输入被旧快照冲掉
+    view: Snapshot,
+    sent: u64,
+}
+
+fn millis_since(epoch: Instant) -> u64 {
+    epoch.elapsed().as_millis() as u64
+}
+
+fn format_uptime(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 3600 {
+        format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    } else {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    }
+}
+
+// 新线程 (包括重启的) 从共享存储的当前状态开始
+fn new_worker(thread_nr: usize, store: Arc<Store>, ctx: egui::Context, epoch: Instant) -> Worker {
+    let (command_tx, inbox) = mpsc::channel::<Message>();
+    let (snapshot_tx, snapshot_rc) = mpsc::channel();
+    let heartbeat = Arc::new(AtomicU64::new(millis_since(epoch)));
+    let beat = heartbeat.clone();
+    let mut state = ThreadState {
+        thread_nr,
+        revision: 0,
+        shared: store.subscribe(command_tx.clone()),
+    };
+    let view = state.snapshot();
+    let handle = std::thread::Builder::new()
+        .name(format!("EguiPanelWorker {thread_nr}"))
+        .spawn(move || {
+            let mut paused = false;
+            let mut queued = Vec::new();
+            loop {
+                beat.store(millis_since(epoch), Ordering::Relaxed);
+                let message = match inbox.recv_timeout(HEARTBEAT) {
+                    Ok(message) => message,
+                    Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+                // 积压的消息一起处理, 只发布一次
+                let mut changed = false;
+                for message in std::iter::once(message).chain(inbox.try_iter()) {
+                    match message {
+                        // 退出时丢弃接收端, 共享存储下次广播时取消订阅
+                        Message::Command(_, Command::Stop) => return,
+                        Message::Command(_, Command::Pause) => paused = true,
+                        Message::Command(_, Command::Resume) => {
+                            paused = false;
+                            for message in queued.drain(..) {
+                                state.handle(message, &store);
+                                changed = true;
+                            }
+                        }
+                        message if paused => queued.push(message),
+                        message => {
+                            state.handle(message, &store);
+                            changed = true;
+                        }
+                    }
+                }
+                if changed {
+                    if snapshot_tx.send(state.snapshot()).is_err() {
+                        break;
+                    }
+                    ctx.request_repaint();
+                }
+            }
+        })
+        .expect("failed to spawn thread");
+    Worker {
+        thread_nr,
+        handle: Some(handle),
+        commands: Some(command_tx),
+        snapshots: snapshot_rc,
+        heartbeat,
+        status: Status::Running,
+        started: Instant::now(),
+        stopped: None,
+        restart_requested: false,
+        view,
+        sent: 0,
+    }
+}
+
+impl Worker {
+    // 只是放进通道, 不会阻塞
+    fn send(&mut self, command: Command) {
+        let Some(commands) = &self.commands else {
+            return;
+        };
+        // 暂停、继续和退出不修改状态, 不占用 revision
+        if !matches!(command, Command::Pause | Command::Resume | Command::Stop) {
+            self.sent += 1;
+        }
+        let _ = commands.send(Message::Command(self.sent, command));
+    }
+
+    fn is_alive(&self) -> bool {
+        matches!(self.status, Status::Running | Status::Paused)
+    }
+
+    fn toggle_pause(&mut self) {
+        if self.status == Status::Paused {
+            self.send(Command::Resume);
+            self.status = Status::Running;
+        } else if self.status == Status::Running {
+            self.send(Command::Pause);
+            self.status = Status::Paused;
+        }
+    }
+
+    // 通知线程处理完当前修改后退出, 不等待
+    fn stop(&mut self) {
+        if self.is_alive() {
+            self.send(Command::Stop);
+            self.commands = None;
+            self.status = Status::Stopping;
+        }
+    }
+
+    fn restart(&mut self) {
+        self.restart_requested = true;
+        self.stop();
+    }
+
+    // 线程退出后 join, 不会阻塞. 需要时重新启动
+    fn update_status(&mut self, store: &Arc<Store>, ctx: &egui::Context, epoch: Instant) {
+        if self.handle.as_ref().is_some_and(JoinHandle::is_finished) {
+            let handle = self.handle.take().expect("checked above");
+            self.commands = None;
+            self.stopped = Some(Instant::now());
+            self.status = match handle.join() {
+                Ok(()) => Status::Stopped,
+                Err(payload) => Status::Panicked(panic_message(payload.as_ref())),
+            };
+        }
+        if self.restart_requested && self.handle.is_none() {
+            *self = new_worker(self.thread_nr, store.clone(), ctx.clone(), epoch);
+        }
+    }
+
+    fn uptime(&self) -> Duration {
+        self.stopped
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started)
+    }
+
+    // 线程列表中的一行
+    fn row_ui(&mut self, ui: &mut egui::Ui, epoch: Instant) {
+        ui.label(format!("#{}", self.thread_nr));
+        match &self.status {
+            Status::Running => match self.stalled_for(epoch) {
+                Some(silent) => ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!("Not responding ({:.1} s)", silent.as_secs_f32()),
+                ),
+                None => ui.label("Running"),
+            },
+            Status::Paused => ui.label("Paused"),
+            Status::Stopping => ui.label("Stopping…"),
+            Status::Stopped => ui.weak("Stopped"),
+            Status::Panicked(message) => {
+                ui.colored_label(ui.visuals().error_fg_color, format!("Panicked: {message}"))
+            }
+        };
+        ui.monospace(format_uptime(self.uptime()));
+        ui.horizontal(|ui| {
+            let pause = if self.status == Status::Paused {
+                "▶ Resume"
+            } else {
+                "⏸ Pause"
+            };
+            if ui
+                .add_enabled(self.is_alive(), egui::Button::new(pause))
+                .clicked()
+            {
+                self.toggle_pause();
+            }
+            if ui
+                .add_enabled(self.is_alive(), egui::Button::new("⏹ Stop"))
+                .clicked()
+            {
+                self.stop();
+            }
+            if ui
+                .add_enabled(!self.restart_requested, egui::Button::new("🔄 Restart"))
+                .clicked()
+            {
+                self.restart();
+            }
+        });
+    }
+
+    fn is_pending(&self) -> bool {
+        self.view.revision < self.sent
+    }
+
+    // 取出最新的快照, 还有未处理的修改时丢弃
+    fn poll(&mut self) {
+        if let Some(snapshot) = self.snapshots.try_iter().last() {
+            if snapshot.revision >= self.sent {
+                self.view = snapshot;
+            }
+        }
+    }
+
+    // 超过 STALL_TIMEOUT 没有心跳时返回卡住的时长
+    fn stalled_for(&self, epoch: Instant) -> Option<Duration> {
+        let last = self.heartbeat.load(Ordering::Relaxed);
+        let silent = Duration::from_millis(millis_since(epoch).saturating_sub(last));
+        (silent > STALL_TIMEOUT).then_some(silent)
+    }
+
+    // 线程的渲染方法, 只使用快照. 线程退出后不再显示
+    fn show(&mut self, ctx: &egui::Context, epoch: Instant) {
+        if !self.is_alive() {
+            return;
+        }
+        let pos = egui::pos2(16.0, 128.0 * (self.thread_nr as f32 + 1.0));
+        egui::Window::new(format!("Background thread {}", self.thread_nr))
+            .default_pos(pos)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let name_label = ui.label("Your name: ");
+                    if ui
+                        .text_edit_singleline(&mut self.view.name)
+                        .labelled_by(name_label.id)
+                        .changed()
+                    {
+                        self.send(Command::SetName(self.view.name.clone()));
+                    }
+                });
+                if ui
+                    .add(egui::Slider::new(&mut self.view.age, 0..=120).text("age"))
+                    .changed()
+                {
+                    self.send(Command::SetAge(self.view.age));
+                }
+                if ui.button("Click each year").clicked() {
+                    self.view.age += 1;
+                    self.send(Command::SetAge(self.view.age));
+                }
+                if ui
+                    .button(format!("Simulate slow work ({} s)", SLOW_WORK.as_secs()))
+                    .clicked()
+                {
+                    self.send(Command::SlowWork(SLOW_WORK));
+                }
+                if ui.button("Simulate panic").clicked() {
+                    self.send(Command::Panic);
+                }
+                ui.label(&self.view.greeting);
+
+                if self.status == Status::Paused {
+                    ui.weak("⏸ Paused, changes are queued");
+                } else if let Some(silent) = self.stalled_for(epoch) {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("⚠ Not responding for {:.1} s", silent.as_secs_f32()),
+                    );
+                } else if self.is_pending() {
+                    ui.weak("⏳ Updating…");
+                } else {
+                    ui.weak("✔ Up to date");
+                }
+            });
+    }
+}
+
+pub struct ThreadApp {
+    ctx: egui::Context,
+    // 心跳时间的基准
+    epoch: Instant,
+    workers: Vec<Worker>,
+    // 所有窗口共享的字段
+    store: Arc<Store>,
+    // 最近几帧 update 的耗时
+    frame_times: VecDeque<Duration>,
+    // 由 Tokio 驱动的面板, 勾选时创建
+    #[cfg(feature = "async")]
+    async_demo: Option<async_mode::AsyncDemo>,
+    #[cfg(feature = "async")]
+    async_error: Option<String>,
+}
+
+impl ThreadApp {
+    pub fn new(ctx: &egui::Context) -> Self {
+        let mut slf = Self {
+            ctx: ctx.clone(),
+            epoch: Instant::now(),
+            workers: Vec::with_capacity(3),
+            store: Arc::new(Store::new(Shared::default())),
+            frame_times: VecDeque::with_capacity(LATENCY_FRAMES),
+            #[cfg(feature = "async")]
+            async_demo: None,
+            #[cfg(feature = "async")]
+            async_error: None,
+        };
+
+        slf.spawn_thread();
+        slf.spawn_thread();
+
+        slf
+    }
+
+    fn spawn_thread(&mut self) {
+        let thread_nr = self.workers.len();
+        let worker = new_worker(thread_nr, self.store.clone(), self.ctx.clone(), self.epoch);
+        self.workers.push(worker);
+    }
+
+    fn frame_time_ui(&self, ui: &mut egui::Ui) {
+        let Some(max) = self.frame_times.iter().max() else {
+            return;
+        };
+        let average = self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32;
+        ui.label(format!(
+            "Frame time: {:.2} ms average, {:.2} ms max (last {} frames)",
+            average.as_secs_f64() * 1000.0,
+            max.as_secs_f64() * 1000.0,
+            self.frame_times.len(),
+        ));
+    }
+}
+
+impl ThreadApp {
+    // 共享存储的修改记录, 最新的在最上面
+    fn change_log_ui(&self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Change log")
+            .default_open(true)
+            .show(ui, |ui| {
+                let log = self.store.log();
+                if log.is_empty() {
+                    ui.weak("No changes yet");
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(160.0)
+                    .show(ui, |ui| {
+                        for change in log.iter().rev() {
+                            ui.monospace(format!(
+                                "#{:<4} thread {}  {}  ({:.1} s ago)",
+                                change.seq,
+                                change.author,
+                                change.field,
+                                change.at.elapsed().as_secs_f32(),
+                            ));
+                        }
+                    });
+            });
+    }
+}
+
+impl ThreadApp {
+    #[cfg(feature = "async")]
+    fn async_ui(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.async_demo.is_some();
+        if ui.checkbox(&mut enabled, "Async panels (Tokio)").changed() {
+            self.async_error = None;
+            if enabled {
+                match async_mode::AsyncDemo::new(ui.ctx()) {
+                    Ok(demo) => self.async_demo = Some(demo),
+                    Err(err) => self.async_error = Some(err.to_string()),
+                }
+            } else {
+                // 丢弃时取消所有异步任务并关闭运行时
+                self.async_demo = None;
+            }
+        }
+        if let Some(demo) = &mut self.async_demo {
+            demo.ui(ui);
+        }
+        if let Some(err) = &self.async_error {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Failed to start async mode: {err}"),
+            );
+        }
+    }
+
+    #[cfg(not(feature = "async"))]
+    fn async_ui(&mut self, ui: &mut egui::Ui) {
+        ui.weak("Build with --features async for Tokio panels");
+    }
+}
+
+impl std::ops::Drop for ThreadApp {
+    // 通知所有线程退出, 最多等待 SHUTDOWN_TIMEOUT, 仍未退出的线程不再等待
+    fn drop(&mut self) {
+        for worker in &mut self.workers {
+            worker.stop();
+        }
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        let running = |workers: &[Worker]| {
+            workers
+                .iter()
+                .any(|w| w.handle.as_ref().is_some_and(|h| !h.is_finished()))
+        };
+        while running(&self.workers) && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        for worker in &mut self.workers {
+            match worker.handle.take() {
+                Some(handle) if handle.is_finished() => {
+                    if let Err(payload) = handle.join() {
+                        log::error!(
+                            "Worker {} panicked: {}",
+                            worker.thread_nr,
+                            panic_message(payload.as_ref())
+                        );
+                    }
+                }
+                Some(_) => log::warn!(
+                    "Worker {} did not stop within {SHUTDOWN_TIMEOUT:?}, detaching it",
+                    worker.thread_nr
+                ),
+                None => {}
+            }
+        }
+    }
+}
+
+impl eframe::App for ThreadApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let frame_start = Instant::now();
+
+        egui::Window::new("Main thread").show(ctx, |ui| {
+            // 主线程按钮, 点击创建一个新的线程
+            if ui.button("Spawn another thread").clicked() {
+                self.spawn_thread();
+            }
+            self.frame_time_ui(ui);
+            ui.separator();
+            egui::Grid::new("workers")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong("Worker");
+                    ui.strong("Status");
+                    ui.strong("Uptime");
+                    ui.end_row();
+                    for worker in &mut self.workers {
+                        worker.row_ui(ui, self.epoch);
+                        ui.end_row();
+                    }
+                });
+            ui.separator();
+            self.change_log_ui(ui);
+            ui.separator();
+            self.async_ui(ui);
+        });
+
+        for worker in &mut self.workers {
+            worker.update_status(&self.store, ctx, self.epoch);
+            worker.poll();
+            worker.show(ctx, self.epoch);
+        }
+        #[cfg(feature = "async")]
+        if let Some(demo) = &mut self.async_demo {
+            demo.show(ctx);
+        }
+        // 没有输入时也要定期刷新, 才能发现卡住的线程
+        ctx.request_repaint_after(STALL_TIMEOUT / 2);
+
+        if self.frame_times.len() == LATENCY_FRAMES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_start.elapsed());
+    }
+}
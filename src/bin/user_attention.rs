@@ -1,14 +1,20 @@
 use eframe::{
-    egui::{Button, CentralPanel, Context, UserAttentionType},
+    egui::{Button, CentralPanel, Context, DragValue, Ui, UserAttentionType},
     CreationContext, NativeOptions,
 };
+use egui_demo::{
+    request_repaint_at, AttentionSchedule, AttentionScheduler, AttentionState, Clock, Countdown,
+    NotificationEvent,
+};
+#[cfg(all(feature = "notifications", target_os = "linux"))]
+use egui_demo::{DbusNotifier, Notification, Urgency};
 
-use std::time::{Duration, SystemTime};
+use std::time::Duration;
 
 fn main() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
     let native_options = NativeOptions {
-        initial_window_size: Some(eframe::egui::vec2(400., 200.)),
+        initial_window_size: Some(eframe::egui::vec2(400., 320.)),
         ..Default::default()
     };
     eframe::run_native(
@@ -22,58 +28,214 @@ fn repr(attention: UserAttentionType) -> String {
     format!("{attention:?}")
 }
 
+// 可选的时间设置: 复选框和秒数
+fn optional_secs(ui: &mut Ui, label: &str, enabled: &mut bool, secs: &mut u64) {
+    ui.horizontal(|ui| {
+        ui.checkbox(enabled, label);
+        ui.add_enabled(
+            *enabled,
+            DragValue::new(secs).clamp_range(1..=600).suffix(" s"),
+        );
+    });
+}
+
+// 注意力请求时同时发送桌面通知, 点击通知上的动作会返回到应用
+#[derive(Default)]
+struct DesktopNotifications {
+    enabled: bool,
+    #[cfg(all(feature = "notifications", target_os = "linux"))]
+    notifier: Option<DbusNotifier>,
+    // 当前显示的通知, 新的请求替换它
+    #[cfg(all(feature = "notifications", target_os = "linux"))]
+    shown: Option<u32>,
+    error: Option<String>,
+    last_event: Option<String>,
+}
+
+impl DesktopNotifications {
+    #[cfg(all(feature = "notifications", target_os = "linux"))]
+    fn requested(&mut self, ctx: &Context, attention: UserAttentionType) {
+        if !self.enabled {
+            return;
+        }
+        if attention == UserAttentionType::Reset {
+            if let (Some(notifier), Some(id)) = (&self.notifier, self.shown.take()) {
+                if let Err(err) = notifier.close(id) {
+                    self.error = Some(err.to_string());
+                }
+            }
+            return;
+        }
+        if self.notifier.is_none() {
+            match DbusNotifier::session("User attention test", ctx) {
+                Ok(notifier) => self.notifier = Some(notifier),
+                Err(err) => {
+                    self.error = Some(err.to_string());
+                    return;
+                }
+            }
+        }
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        let urgency = if attention == UserAttentionType::Critical {
+            Urgency::Critical
+        } else {
+            Urgency::Normal
+        };
+        let notification = Notification::new("User attention test")
+            .body(format!("Attention requested ({})", repr(attention)))
+            .urgency(urgency)
+            .action("default", "Show")
+            .action("stop", "Stop reminders");
+        match notifier.notify(&notification, self.shown.unwrap_or(0)) {
+            Ok(id) => {
+                self.shown = Some(id);
+                self.error = None;
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+    }
+
+    #[cfg(not(all(feature = "notifications", target_os = "linux")))]
+    fn requested(&mut self, _ctx: &Context, _attention: UserAttentionType) {}
+
+    // 只返回当前显示的通知的事件, 信号是广播的, 其他程序的通知
+    // 和已经关闭的通知的事件都忽略
+    #[cfg(all(feature = "notifications", target_os = "linux"))]
+    fn poll(&mut self) -> Vec<NotificationEvent> {
+        let Some(notifier) = &self.notifier else {
+            return Vec::new();
+        };
+        let mut events = notifier.poll_events();
+        events.retain(|event| {
+            let (NotificationEvent::ActionInvoked { id, .. }
+            | NotificationEvent::Closed { id, .. }) = event;
+            let shown = self.shown == Some(*id);
+            if shown && matches!(event, NotificationEvent::Closed { .. }) {
+                self.shown = None;
+            }
+            shown
+        });
+        if let Some(event) = events.last() {
+            self.last_event = Some(format!("{event:?}"));
+        }
+        events
+    }
+
+    #[cfg(not(all(feature = "notifications", target_os = "linux")))]
+    fn poll(&mut self) -> Vec<NotificationEvent> {
+        Vec::new()
+    }
+
+    fn ui(&mut self, ui: &mut Ui) {
+        if cfg!(all(feature = "notifications", target_os = "linux")) {
+            ui.checkbox(&mut self.enabled, "Post a desktop notification");
+        } else {
+            ui.weak("Build with --features notifications for desktop notifications");
+        }
+        if let Some(err) = &self.error {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Notification failed: {err}"),
+            );
+        }
+        if let Some(event) = &self.last_event {
+            ui.weak(format!("Last notification event: {event}"));
+        }
+    }
+}
+
 struct Application {
+    // select, 失去焦点时任务栏图表闪烁的行为
     attention: UserAttentionType,
-    request_at: Option<SystemTime>,
-
+    delay_secs: u64,
+    repeat: bool,
+    repeat_secs: u64,
+    // 0 表示不限次数
+    max_requests: u32,
+    escalate: bool,
+    escalate_secs: u64,
+    // checkbox, 重置
     auto_reset: bool,
-    reset_at: Option<SystemTime>,
+    reset_secs: u64,
+    scheduler: AttentionScheduler,
+    notifications: DesktopNotifications,
 }
 
 impl Application {
     fn new(_cc: &CreationContext<'_>) -> Self {
         Self {
-            // select, 失去焦点时任务栏图表闪烁的行为
             attention: UserAttentionType::Informational,
-            // 闪烁的时间
-            request_at: None,
-            // checkbox, 重置
+            delay_secs: 2,
+            repeat: false,
+            repeat_secs: 10,
+            max_requests: 0,
+            escalate: false,
+            escalate_secs: 30,
             auto_reset: false,
-            // 重置时间
-            reset_at: None,
+            reset_secs: 3,
+            scheduler: AttentionScheduler::default(),
+            notifications: DesktopNotifications::default(),
         }
     }
 
-    fn attention_reset_timeout() -> Duration {
-        Duration::from_secs(3)
-    }
-
-    fn attention_request_timeout() -> Duration {
-        Duration::from_secs(2)
+    fn schedule(&self) -> AttentionSchedule {
+        let mut schedule =
+            AttentionSchedule::new(Duration::from_secs(self.delay_secs)).kind(self.attention);
+        if self.repeat {
+            schedule = schedule.repeat_every(Duration::from_secs(self.repeat_secs));
+            if self.max_requests > 0 {
+                schedule = schedule.max_requests(self.max_requests);
+            }
+        }
+        if self.escalate {
+            schedule = schedule.escalate_after(Duration::from_secs(self.escalate_secs));
+        }
+        if self.auto_reset {
+            schedule = schedule.reset_after(Duration::from_secs(self.reset_secs));
+        }
+        schedule
     }
 
-    fn repaint_max_timeout() -> Duration {
-        Duration::from_secs(1)
+    fn status_ui(&self, ui: &mut Ui) {
+        let next = self.scheduler.time_until_next();
+        ui.horizontal(|ui| {
+            match self.scheduler.state() {
+                AttentionState::Idle => ui.label("Idle"),
+                AttentionState::Waiting if next.is_none() => {
+                    ui.label("Waiting for the window to lose focus")
+                }
+                AttentionState::Waiting => ui.label("Waiting"),
+                AttentionState::Requested {
+                    requests,
+                    escalated,
+                } => {
+                    let escalated = if escalated { " (escalated)" } else { "" };
+                    ui.label(format!("Requested {requests} times{escalated}"))
+                }
+            };
+            if let Some(next) = next.filter(|next| !next.is_zero()) {
+                ui.add(Countdown::new(next).prefix("next in "));
+            }
+        });
     }
 }
 
 impl eframe::App for Application {
     fn update(&mut self, ctx: &Context, frame: &mut eframe::Frame) {
-        if let Some(request_at) = self.request_at {
-            if request_at < SystemTime::now() {
-                self.request_at = None;
-                frame.request_user_attention(self.attention);
-                if self.auto_reset {
-                    self.auto_reset = false;
-                    self.reset_at = Some(SystemTime::now() + Self::attention_reset_timeout());
-                }
-            }
+        self.scheduler.set_focused(frame.info().window_info.focused);
+        if let Some(attention) = self.scheduler.poll() {
+            frame.request_user_attention(attention);
+            self.notifications.requested(ctx, attention);
         }
-
-        if let Some(reset_at) = self.reset_at {
-            if reset_at < SystemTime::now() {
-                self.reset_at = None;
-                frame.request_user_attention(UserAttentionType::Reset);
+        for event in self.notifications.poll() {
+            match event {
+                NotificationEvent::ActionInvoked { action, .. } => match action.as_str() {
+                    "stop" => self.scheduler.cancel(),
+                    _ => frame.focus(),
+                },
+                NotificationEvent::Closed { .. } => {}
             }
         }
 
@@ -92,46 +254,59 @@ impl eframe::App for Application {
                             }
                         })
                 });
-
-                let button_enabled = self.request_at.is_none() && self.reset_at.is_none();
-                let button_text = if button_enabled {
-                    format!(
-                        "Request in {} seconds",
-                        Self::attention_request_timeout().as_secs()
-                    )
-                } else {
-                    match self.reset_at {
-                        None => "Unfocus the window, fast!".to_owned(),
-                        Some(t) => {
-                            if let Ok(elapsed) = t.duration_since(SystemTime::now()) {
-                                format!("Resetting attention in {} s...", elapsed.as_secs())
-                            } else {
-                                "Resetting attention...".to_owned()
-                            }
-                        }
-                    }
-                };
-
-                let resp = ui
-                    .add_enabled(button_enabled, Button::new(button_text))
-                    .on_hover_text_at_pointer(
-                        "After clicking, unfocus the application's window to see the effect",
+                ui.horizontal(|ui| {
+                    ui.label("Delay:");
+                    ui.add(
+                        DragValue::new(&mut self.delay_secs)
+                            .clamp_range(0..=600)
+                            .suffix(" s"),
                     );
-
-                ui.checkbox(
+                });
+                optional_secs(ui, "Repeat every", &mut self.repeat, &mut self.repeat_secs);
+                ui.add_enabled_ui(self.repeat, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label("Max requests (0 = unlimited):");
+                        ui.add(DragValue::new(&mut self.max_requests).clamp_range(0..=100));
+                    });
+                });
+                optional_secs(
+                    ui,
+                    "Escalate to Critical after",
+                    &mut self.escalate,
+                    &mut self.escalate_secs,
+                );
+                optional_secs(
+                    ui,
+                    "Reset after",
                     &mut self.auto_reset,
-                    format!(
-                        "Reset after {} seconds",
-                        Self::attention_reset_timeout().as_secs()
-                    ),
+                    &mut self.reset_secs,
                 );
+                self.notifications.ui(ui);
 
-                if resp.clicked() {
-                    self.request_at = Some(SystemTime::now() + Self::attention_request_timeout());
-                }
+                ui.separator();
+                let idle = self.scheduler.state() == AttentionState::Idle;
+                ui.horizontal(|ui| {
+                    let resp = ui
+                        .add_enabled(idle, Button::new("Schedule"))
+                        .on_hover_text_at_pointer(
+                            "After clicking, unfocus the application's window to see the effect",
+                        );
+                    if resp.clicked() {
+                        self.scheduler.schedule(self.schedule());
+                    }
+                    if ui.add_enabled(!idle, Button::new("Cancel")).clicked() {
+                        self.scheduler.cancel();
+                    }
+                });
+                self.status_ui(ui);
             });
         });
 
-        ctx.request_repaint_after(Self::repaint_max_timeout());
+        // 只在下一个事件到期时重绘, 空闲时不再每秒唤醒
+        request_repaint_at(
+            ctx,
+            self.scheduler.clock().now(),
+            self.scheduler.next_deadline(),
+        );
     }
 }
@@ -0,0 +1,220 @@
+use egui::accesskit::Role;
+use egui::{Align2, Color32, Id, Key, LayerId, Modifiers, Order, Vec2};
+
+use crate::a11y;
+use crate::i18n::tr;
+use crate::ime;
+
+// 模态遮罩层, 位于面板之上、窗口之下
+pub fn modal_backdrop(ctx: &egui::Context) {
+    let painter = ctx.layer_painter(LayerId::new(
+        Order::PanelResizeLine,
+        Id::new("modal_backdrop"),
+    ));
+    painter.rect_filled(ctx.screen_rect(), 0.0, Color32::from_black_alpha(128));
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DialogId(u64);
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DialogResponse {
+    // 点击的按钮下标, Enter 对应第一个按钮
+    Button(usize),
+    // 输入框提交的内容
+    Submitted(String),
+    // Escape 或取消
+    Cancelled,
+}
+
+enum DialogKind {
+    Confirm {
+        message: String,
+        buttons: Vec<String>,
+    },
+    Prompt {
+        message: String,
+        input: String,
+    },
+    Error {
+        message: String,
+        details: Option<String>,
+    },
+}
+
+struct Dialog {
+    id: DialogId,
+    title: String,
+    kind: DialogKind,
+    // 输入框是否已获取过焦点
+    focused: bool,
+}
+
+// 对话框栈, 只有栈顶的对话框可以交互
+#[derive(Default)]
+pub struct Dialogs {
+    stack: Vec<Dialog>,
+    next_id: u64,
+    responses: Vec<(DialogId, DialogResponse)>,
+}
+
+impl Dialogs {
+    fn push(&mut self, title: impl Into<String>, kind: DialogKind) -> DialogId {
+        let id = DialogId(self.next_id);
+        self.next_id += 1;
+        self.stack.push(Dialog {
+            id,
+            title: title.into(),
+            kind,
+            focused: false,
+        });
+        id
+    }
+
+    pub fn confirm(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        buttons: &[&str],
+    ) -> DialogId {
+        let kind = DialogKind::Confirm {
+            message: message.into(),
+            buttons: buttons.iter().map(|b| (*b).to_owned()).collect(),
+        };
+        self.push(title, kind)
+    }
+
+    pub fn prompt(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        initial: impl Into<String>,
+    ) -> DialogId {
+        let kind = DialogKind::Prompt {
+            message: message.into(),
+            input: initial.into(),
+        };
+        self.push(title, kind)
+    }
+
+    pub fn error(
+        &mut self,
+        title: impl Into<String>,
+        message: impl Into<String>,
+        details: Option<String>,
+    ) -> DialogId {
+        let kind = DialogKind::Error {
+            message: message.into(),
+            details,
+        };
+        self.push(title, kind)
+    }
+
+    pub fn is_open(&self) -> bool {
+        !self.stack.is_empty()
+    }
+
+    pub fn contains(&self, id: DialogId) -> bool {
+        self.stack.iter().any(|dialog| dialog.id == id)
+    }
+
+    // 取出对话框的结果, 每个结果只会被取出一次
+    pub fn take_response(&mut self, id: DialogId) -> Option<DialogResponse> {
+        let index = self.responses.iter().position(|(i, _)| *i == id)?;
+        Some(self.responses.remove(index).1)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = self.stack.last_mut() else {
+            return;
+        };
+
+        modal_backdrop(ctx);
+
+        let mut response = ctx.input_mut(|i| {
+            if i.consume_key(Modifiers::NONE, Key::Escape) {
+                Some(DialogResponse::Cancelled)
+            } else {
+                None
+            }
+        });
+        let enter = ctx.input_mut(|i| i.consume_key(Modifiers::NONE, Key::Enter));
+
+        let window_id = Id::new(("dialog", dialog.id));
+        // 读屏软件把对话框中的控件作为一组朗读, 并且知道它是模态的
+        let role = match dialog.kind {
+            DialogKind::Error { .. } => Role::AlertDialog,
+            _ => Role::Dialog,
+        };
+        let node_id = window_id.with("accesskit");
+        ctx.accesskit_node_builder(node_id, |builder| builder.set_modal());
+        egui::Window::new(&dialog.title)
+            .id(window_id)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                a11y::group(ctx, node_id, role, &dialog.title, || {
+                    match &mut dialog.kind {
+                        DialogKind::Confirm { message, buttons } => {
+                            ui.label(message.as_str());
+                            ui.add_space(8.);
+                            ui.horizontal(|ui| {
+                                for (index, button) in buttons.iter().enumerate() {
+                                    if ui.button(button.as_str()).clicked() {
+                                        response = Some(DialogResponse::Button(index));
+                                    }
+                                }
+                            });
+                            if enter && !buttons.is_empty() {
+                                response = Some(DialogResponse::Button(0));
+                            }
+                        }
+                        DialogKind::Prompt { message, input } => {
+                            ui.label(message.as_str());
+                            let edit = ime::show(ui, egui::TextEdit::singleline(input)).response;
+                            if !dialog.focused {
+                                edit.request_focus();
+                                dialog.focused = true;
+                            }
+                            ui.add_space(8.);
+                            ui.horizontal(|ui| {
+                                if ui.button(tr("ok")).clicked() || enter {
+                                    response = Some(DialogResponse::Submitted(input.clone()));
+                                }
+                                if ui.button(tr("cancel")).clicked() {
+                                    response = Some(DialogResponse::Cancelled);
+                                }
+                            });
+                        }
+                        DialogKind::Error { message, details } => {
+                            ui.label(
+                                egui::RichText::new(message.as_str())
+                                    .color(ui.visuals().error_fg_color),
+                            );
+                            if let Some(details) = details {
+                                egui::CollapsingHeader::new(tr("details"))
+                                    .id_source("details")
+                                    .show(ui, |ui| {
+                                        ui.monospace(details.as_str());
+                                        if ui.small_button(format!("📋 {}", tr("copy"))).clicked()
+                                        {
+                                            ui.output_mut(|o| o.copied_text = details.clone());
+                                        }
+                                    });
+                            }
+                            ui.add_space(8.);
+                            if ui.button(tr("ok")).clicked() || enter {
+                                response = Some(DialogResponse::Button(0));
+                            }
+                        }
+                    }
+                })
+            });
+
+        if let Some(response) = response {
+            let dialog = self.stack.pop().expect("dialog stack is not empty");
+            self.responses.push((dialog.id, response));
+        }
+    }
+}
@@ -0,0 +1,863 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use egui::text::CCursor;
+use egui::text_edit::{CCursorRange, TextEditState};
+use egui::{Align2, Galley, Id, Key, RichText, ScrollArea, TextStyle, Ui};
+use regex::{NoExpand, Regex, RegexBuilder};
+
+use crate::a11y::Accessible;
+use crate::highlight::{add_background, highlight, CodeTheme, Language};
+use crate::i18n::{tr, tr_args, tr_plural};
+use crate::ime;
+use crate::toast::Toasts;
+
+// 超过该大小的文本按行虚拟滚动, 不再每帧整体排版
+const LARGE_FILE_BYTES: usize = 256 * 1024;
+// 该时间内的连续输入合并为一次撤销
+const UNDO_COALESCE_SECS: f64 = 1.0;
+const MAX_UNDO: usize = 200;
+
+// 一步修改: 在 start (字节) 处把 removed 替换为 inserted.
+// 只保存变化的部分, 大文本每次输入不会复制整个缓冲区
+#[derive(Clone, Debug, PartialEq)]
+struct Change {
+    start: usize,
+    removed: String,
+    inserted: String,
+}
+
+impl Change {
+    // 比较前后文本的公共前缀和后缀得到修改, 没有变化时返回 None
+    fn diff(old: &str, new: &str) -> Option<Self> {
+        if old == new {
+            return None;
+        }
+        let mut prefix = old
+            .bytes()
+            .zip(new.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(prefix) || !new.is_char_boundary(prefix) {
+            prefix -= 1;
+        }
+        let max_suffix = old.len().min(new.len()) - prefix;
+        let mut suffix = old
+            .bytes()
+            .rev()
+            .zip(new.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix)
+        {
+            suffix -= 1;
+        }
+        Some(Self {
+            start: prefix,
+            removed: old[prefix..old.len() - suffix].to_owned(),
+            inserted: new[prefix..new.len() - suffix].to_owned(),
+        })
+    }
+
+    fn apply(&self, text: &mut String) {
+        text.replace_range(self.start..self.start + self.removed.len(), &self.inserted);
+    }
+
+    fn revert(&self, text: &mut String) {
+        text.replace_range(self.start..self.start + self.inserted.len(), &self.removed);
+    }
+
+    // 合并紧接着的下一步修改. middle 为两步之间的文本
+    fn merge(&mut self, next: &Change, middle: &str) {
+        let start = self.start.min(next.start);
+        let end = (self.start + self.inserted.len()).max(next.start + next.removed.len());
+        let mut removed = middle[start..self.start].to_owned();
+        removed.push_str(&self.removed);
+        removed.push_str(&middle[self.start + self.inserted.len()..end]);
+        let mut inserted = middle[start..next.start].to_owned();
+        inserted.push_str(&next.inserted);
+        inserted.push_str(&middle[next.start + next.removed.len()..end]);
+        *self = Self {
+            start,
+            removed,
+            inserted,
+        };
+    }
+}
+
+#[derive(Default)]
+struct Find {
+    open: bool,
+    query: String,
+    replacement: String,
+    regex: bool,
+    case_sensitive: bool,
+    // 当前选中的匹配项
+    current: usize,
+    // 匹配结果 (字节范围), 文本或查询变化时重新计算
+    matches: Vec<Range<usize>>,
+    cache_key: Option<(u64, String, bool, bool)>,
+    error: Option<String>,
+}
+
+impl Find {
+    fn pattern(&self) -> Result<Regex, regex::Error> {
+        let pattern = if self.regex {
+            self.query.clone()
+        } else {
+            regex::escape(&self.query)
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+    }
+
+    fn update_matches(&mut self, text: &str, version: u64) {
+        let key = (version, self.query.clone(), self.regex, self.case_sensitive);
+        if self.cache_key.as_ref() == Some(&key) {
+            return;
+        }
+        self.cache_key = Some(key);
+        self.matches.clear();
+        self.error = None;
+        if self.query.is_empty() {
+            return;
+        }
+        match self.pattern() {
+            Ok(re) => {
+                self.matches = re
+                    .find_iter(text)
+                    .filter(|m| !m.is_empty())
+                    .map(|m| m.range())
+                    .collect();
+            }
+            Err(err) => self.error = Some(err.to_string()),
+        }
+        if self.current >= self.matches.len() {
+            self.current = 0;
+        }
+    }
+}
+
+pub struct Editor {
+    id: Id,
+    text: String,
+    // 上一次记录修改后的文本, 用于计算下一步修改
+    committed: String,
+    version: u64,
+    path: Option<PathBuf>,
+    // 打开或保存文件时的内容, 用于判断文件是否被修改
+    file_text: String,
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+    last_edit_time: f64,
+    word_wrap: bool,
+    language: Language,
+    max_height: f32,
+    find: Find,
+    // 光标位置 (字符下标) 和对应的行列 (从 1 开始)
+    cursor: usize,
+    line: usize,
+    column: usize,
+    // 每行起始字节, 用于行号和大文本模式
+    line_starts: Vec<usize>,
+    // 大文本模式下正在编辑的行
+    editing_line: Option<(usize, String)>,
+    scroll_to_line: Option<usize>,
+    // 下一帧需要设置的选区 (字符下标)
+    pending_selection: Option<(usize, usize)>,
+    has_focus: bool,
+}
+
+impl Editor {
+    pub fn new(id_source: impl std::hash::Hash, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let mut editor = Self {
+            id: Id::new(id_source),
+            committed: text.clone(),
+            file_text: text.clone(),
+            text,
+            version: 0,
+            path: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit_time: f64::NEG_INFINITY,
+            word_wrap: true,
+            language: Language::Plain,
+            max_height: 300.0,
+            find: Find::default(),
+            cursor: 0,
+            line: 1,
+            column: 1,
+            line_starts: Vec::new(),
+            editing_line: None,
+            scroll_to_line: None,
+            pending_selection: None,
+            has_focus: false,
+        };
+        editor.update_line_starts();
+        editor
+    }
+
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
+    }
+
+    pub fn max_height(mut self, max_height: f32) -> Self {
+        self.max_height = max_height;
+        self
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // 替换全部内容并清空撤销历史
+    pub fn set_text(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.committed = self.text.clone();
+        self.undo.clear();
+        self.redo.clear();
+        self.editing_line = None;
+        self.text_changed();
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn is_modified(&self) -> bool {
+        self.text != self.file_text
+    }
+
+    pub fn is_large(&self) -> bool {
+        self.text.len() > LARGE_FILE_BYTES
+    }
+
+    pub fn has_focus(&self) -> bool {
+        self.has_focus
+    }
+
+    pub fn open(&mut self, path: &Path) -> std::io::Result<()> {
+        let text = std::fs::read_to_string(path)?;
+        self.set_text(text);
+        self.file_text = self.text.clone();
+        self.path = Some(path.to_owned());
+        self.language = Language::from_path(path);
+        Ok(())
+    }
+
+    pub fn save_as(&mut self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, &self.text)?;
+        self.file_text = self.text.clone();
+        self.path = Some(path.to_owned());
+        self.language = Language::from_path(path);
+        Ok(())
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(change) = self.undo.pop() {
+            change.revert(&mut self.text);
+            change.revert(&mut self.committed);
+            let cursor = self.char_index(change.start + change.removed.len());
+            self.redo.push(change);
+            self.after_undo(cursor);
+        }
+    }
+
+    pub fn redo(&mut self) {
+        if let Some(change) = self.redo.pop() {
+            change.apply(&mut self.text);
+            change.apply(&mut self.committed);
+            let cursor = self.char_index(change.start + change.inserted.len());
+            self.undo.push(change);
+            self.after_undo(cursor);
+        }
+    }
+
+    fn after_undo(&mut self, cursor: usize) {
+        self.pending_selection = Some((cursor, cursor));
+        // 撤销后的下一次输入作为新的一步
+        self.last_edit_time = f64::NEG_INFINITY;
+        self.editing_line = None;
+        self.text_changed();
+    }
+
+    // 文本被修改后调用, 间隔较短的连续输入合并为一步撤销
+    fn record_edit(&mut self, now: f64, coalesce: bool) {
+        let Some(change) = Change::diff(&self.committed, &self.text) else {
+            return;
+        };
+        match self.undo.last_mut() {
+            Some(last) if coalesce && now - self.last_edit_time <= UNDO_COALESCE_SECS => {
+                last.merge(&change, &self.committed);
+            }
+            _ => {
+                self.undo.push(change.clone());
+                if self.undo.len() > MAX_UNDO {
+                    self.undo.remove(0);
+                }
+            }
+        }
+        change.apply(&mut self.committed);
+        self.last_edit_time = if coalesce { now } else { f64::NEG_INFINITY };
+        self.redo.clear();
+        self.text_changed();
+    }
+
+    fn text_changed(&mut self) {
+        self.version += 1;
+        self.update_line_starts();
+    }
+
+    fn update_line_starts(&mut self) {
+        self.line_starts.clear();
+        self.line_starts.push(0);
+        self.line_starts
+            .extend(self.text.match_indices('\n').map(|(i, _)| i + 1));
+    }
+
+    fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    fn line_range(&self, line: usize) -> Range<usize> {
+        let start = self.line_starts[line];
+        let end = self
+            .line_starts
+            .get(line + 1)
+            .map_or(self.text.len(), |next| next - 1);
+        start..end
+    }
+
+    fn line_of_byte(&self, byte: usize) -> usize {
+        self.line_starts.partition_point(|&start| start <= byte) - 1
+    }
+
+    fn char_index(&self, byte: usize) -> usize {
+        self.text[..byte].chars().count()
+    }
+
+    fn byte_index(&self, char_index: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(char_index)
+            .map_or(self.text.len(), |(i, _)| i)
+    }
+
+    fn set_cursor(&mut self, cursor: usize) {
+        self.cursor = cursor;
+        let byte = self.byte_index(cursor);
+        let line = self.line_of_byte(byte);
+        self.line = line + 1;
+        self.column = self.text[self.line_starts[line]..byte].chars().count() + 1;
+    }
+
+    fn select_match(&mut self, index: usize) {
+        let Some(range) = self.find.matches.get(index).cloned() else {
+            return;
+        };
+        self.find.current = index;
+        let start = self.char_index(range.start);
+        let end = start + self.text[range.clone()].chars().count();
+        self.pending_selection = Some((start, end));
+        self.scroll_to_line = Some(self.line_of_byte(range.start));
+        self.set_cursor(end);
+    }
+
+    fn replace_current(&mut self, now: f64) {
+        let Some(range) = self.find.matches.get(self.find.current).cloned() else {
+            return;
+        };
+        let Ok(re) = self.find.pattern() else {
+            return;
+        };
+        let replacement = if self.find.regex {
+            // 支持 $1 等捕获组引用
+            match re.captures_at(&self.text, range.start) {
+                Some(caps) => {
+                    let mut dst = String::new();
+                    caps.expand(&self.find.replacement, &mut dst);
+                    dst
+                }
+                None => return,
+            }
+        } else {
+            self.find.replacement.clone()
+        };
+        self.text.replace_range(range, &replacement);
+        self.record_edit(now, false);
+        self.find.update_matches(&self.text, self.version);
+        self.select_match(self.find.current);
+    }
+
+    fn replace_all(&mut self, now: f64) {
+        let Ok(re) = self.find.pattern() else {
+            return;
+        };
+        if self.find.query.is_empty() {
+            return;
+        }
+        let replaced = if self.find.regex {
+            re.replace_all(&self.text, self.find.replacement.as_str())
+        } else {
+            re.replace_all(&self.text, NoExpand(&self.find.replacement))
+        }
+        .into_owned();
+        if replaced != self.text {
+            self.text = replaced;
+            self.record_edit(now, false);
+        }
+    }
+
+    fn open_dialog(&mut self, toasts: &mut Toasts) {
+        if let Some(path) = rfd::FileDialog::new().pick_file() {
+            match self.open(&path) {
+                Ok(()) => toasts.info(tr_args("file-opened", &[("path", &path.display())])),
+                Err(err) => toasts.error(tr_args(
+                    "file-open-failed",
+                    &[("path", &path.display()), ("error", &err)],
+                )),
+            }
+        }
+    }
+
+    fn save_dialog(&mut self, toasts: &mut Toasts, save_as: bool) {
+        let path = match (&self.path, save_as) {
+            (Some(path), false) => Some(path.clone()),
+            _ => rfd::FileDialog::new().save_file(),
+        };
+        if let Some(path) = path {
+            match self.save_as(&path) {
+                Ok(()) => toasts.success(tr_args("file-saved", &[("path", &path.display())])),
+                Err(err) => toasts.error(tr_args(
+                    "file-save-failed",
+                    &[("path", &path.display()), ("error", &err)],
+                )),
+            }
+        }
+    }
+
+    // 保存到当前文件, 没有路径时选择文件
+    pub fn save(&mut self, toasts: &mut Toasts) {
+        self.save_dialog(toasts, false);
+    }
+
+    pub fn open_find(&mut self) {
+        self.find.open = true;
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        let now = ui.input(|i| i.time);
+
+        self.toolbar_ui(ui, toasts);
+        if self.find.open {
+            self.find_ui(ui, now);
+        }
+
+        if self.is_large() {
+            self.large_text_ui(ui, now);
+        } else {
+            self.text_edit_ui(ui, now);
+        }
+
+        self.status_ui(ui);
+    }
+
+    fn toolbar_ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            if ui.button(format!("📂 {}", tr("open"))).clicked() {
+                self.open_dialog(toasts);
+            }
+            if ui.button(format!("💾 {}", tr("save"))).clicked() {
+                self.save_dialog(toasts, false);
+            }
+            if ui.button(tr("save-as")).clicked() {
+                self.save_dialog(toasts, true);
+            }
+            ui.separator();
+            if ui
+                .add_enabled(self.can_undo(), egui::Button::new("⟲"))
+                .on_hover_text(tr("undo"))
+                .accessible_name(tr("undo"))
+                .clicked()
+            {
+                self.undo();
+            }
+            if ui
+                .add_enabled(self.can_redo(), egui::Button::new("⟳"))
+                .on_hover_text(tr("redo"))
+                .accessible_name(tr("redo"))
+                .clicked()
+            {
+                self.redo();
+            }
+            ui.separator();
+            ui.add_enabled_ui(!self.is_large(), |ui| {
+                ui.checkbox(&mut self.word_wrap, tr("editor-word-wrap"))
+                    .on_disabled_hover_text(tr("editor-word-wrap-disabled"));
+            });
+            ui.toggle_value(&mut self.find.open, format!("🔍 {}", tr("editor-find")));
+            egui::ComboBox::from_id_source(self.id.with("language"))
+                .selected_text(self.language.name())
+                .show_ui(ui, |ui| {
+                    for language in Language::ALL {
+                        ui.selectable_value(&mut self.language, language, language.name());
+                    }
+                })
+                .response
+                .accessible_name(tr("editor-language"));
+        });
+    }
+
+    fn find_ui(&mut self, ui: &mut Ui, now: f64) {
+        self.find.update_matches(&self.text, self.version);
+
+        ui.horizontal(|ui| {
+            ui.label(tr("editor-find-label"));
+            let query = ime::show(ui, egui::TextEdit::singleline(&mut self.find.query)).response;
+            let mut next = query.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            ui.checkbox(&mut self.find.regex, ".*")
+                .on_hover_text(tr("editor-regex"))
+                .accessible_name(tr("editor-regex"));
+            ui.checkbox(&mut self.find.case_sensitive, "Aa")
+                .on_hover_text(tr("editor-match-case"))
+                .accessible_name(tr("editor-match-case"));
+            let count = self.find.matches.len();
+            if ui
+                .add_enabled(count > 0, egui::Button::new("⏶"))
+                .on_hover_text(tr("editor-previous-match"))
+                .accessible_name(tr("editor-previous-match"))
+                .clicked()
+            {
+                self.select_match((self.find.current + count - 1) % count);
+            }
+            if ui
+                .add_enabled(count > 0, egui::Button::new("⏷"))
+                .on_hover_text(tr("editor-next-match"))
+                .accessible_name(tr("editor-next-match"))
+                .clicked()
+            {
+                next = true;
+            }
+            if next && count > 0 {
+                self.select_match((self.find.current + 1) % count);
+            }
+            match &self.find.error {
+                Some(err) => {
+                    ui.label(
+                        RichText::new(tr("editor-invalid-pattern"))
+                            .color(ui.visuals().error_fg_color),
+                    )
+                    .on_hover_text(err.as_str());
+                }
+                None if count > 0 => {
+                    ui.label(format!("{}/{count}", self.find.current + 1));
+                }
+                None => {
+                    ui.weak(tr("editor-no-matches"));
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("editor-replace-label"));
+            ime::show(ui, egui::TextEdit::singleline(&mut self.find.replacement));
+            let has_matches = !self.find.matches.is_empty();
+            if ui
+                .add_enabled(has_matches, egui::Button::new(tr("editor-replace")))
+                .clicked()
+            {
+                self.replace_current(now);
+            }
+            if ui
+                .add_enabled(has_matches, egui::Button::new(tr("editor-replace-all")))
+                .clicked()
+            {
+                self.replace_all(now);
+            }
+        });
+    }
+
+    fn text_id(&self) -> Id {
+        self.id.with("text")
+    }
+
+    fn text_edit_ui(&mut self, ui: &mut Ui, now: f64) {
+        let text_id = self.text_id();
+        if let Some((start, end)) = self.pending_selection.take() {
+            let mut state = TextEditState::load(ui.ctx(), text_id).unwrap_or_default();
+            state.set_ccursor_range(Some(CCursorRange::two(
+                CCursor::new(start),
+                CCursor::new(end),
+            )));
+            state.store(ui.ctx(), text_id);
+            ui.memory_mut(|m| m.request_focus(text_id));
+        }
+
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let word_wrap = self.word_wrap;
+        let highlights = if self.find.open {
+            self.find.matches.as_slice()
+        } else {
+            &[]
+        };
+        let highlight_color = ui.visuals().selection.bg_fill.gamma_multiply(0.5);
+        let text_len = self.text.len();
+        let theme = CodeTheme::from_style(ui.style());
+        let language = self.language;
+        let mut layouter = |ui: &Ui, string: &str, wrap_width: f32| -> Arc<Galley> {
+            let mut job = highlight(ui.ctx(), &theme, string, language);
+            // 本帧内容已被修改时匹配结果失效, 不再标记
+            let valid = string.len() == text_len
+                && highlights
+                    .iter()
+                    .all(|r| string.is_char_boundary(r.start) && string.is_char_boundary(r.end));
+            if valid {
+                add_background(&mut job, highlights, highlight_color);
+            }
+            job.wrap.max_width = if word_wrap { wrap_width } else { f32::INFINITY };
+            ui.fonts(|f| f.layout_job(job))
+        };
+
+        let gutter_width = {
+            let digits = self.line_count().to_string().len() as f32;
+            let char_width = ui.fonts(|f| f.glyph_width(&font_id, '0'));
+            digits * char_width + 12.0
+        };
+
+        let scroll = if word_wrap {
+            ScrollArea::vertical()
+        } else {
+            ScrollArea::both()
+        };
+        let output = scroll
+            .id_source(self.id.with("scroll"))
+            .max_height(self.max_height)
+            .show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    let (gutter_rect, _) =
+                        ui.allocate_exact_size(egui::vec2(gutter_width, 0.0), egui::Sense::hover());
+                    let edit = egui::TextEdit::multiline(&mut self.text)
+                        .id(text_id)
+                        .code_editor()
+                        .lock_focus(true)
+                        .desired_width(if word_wrap {
+                            ui.available_width()
+                        } else {
+                            f32::INFINITY
+                        })
+                        .layouter(&mut layouter);
+                    let output = ime::show(ui, edit);
+                    output.response.clone().accessible_name(tr("editor-text"));
+                    paint_line_numbers(ui, &output, gutter_rect.right() - 4.0, &font_id);
+                    output
+                })
+                .inner
+            })
+            .inner;
+
+        self.has_focus = output.response.has_focus();
+        // 组字过程中的文字不记入撤销历史, 上屏后再记录
+        if output.response.changed() && !ime::is_composing(ui.ctx(), text_id) {
+            self.record_edit(now, true);
+        }
+        if let Some(cursor_range) = output.cursor_range {
+            self.set_cursor(cursor_range.primary.ccursor.index);
+        }
+    }
+
+    // 大文本只排版可见的行, 点击某一行后单独编辑
+    fn large_text_ui(&mut self, ui: &mut Ui, now: f64) {
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let row_height = ui.fonts(|f| f.row_height(&font_id)) + 4.0;
+        let digits = self.line_count().to_string().len();
+        let theme = CodeTheme::from_style(ui.style());
+
+        let mut scroll = ScrollArea::both()
+            .id_source(self.id.with("scroll"))
+            .max_height(self.max_height)
+            .auto_shrink([false, true]);
+        if let Some(line) = self.scroll_to_line.take() {
+            let spacing = ui.spacing().item_spacing.y;
+            scroll = scroll.vertical_scroll_offset(line as f32 * (row_height + spacing));
+        }
+
+        let mut commit_line = None;
+        let mut start_editing = None;
+        let mut has_focus = false;
+        scroll.show_rows(ui, row_height, self.line_count(), |ui, rows| {
+            for line in rows {
+                ui.horizontal(|ui| {
+                    ui.set_height(row_height);
+                    ui.label(
+                        RichText::new(format!("{:>digits$}", line + 1))
+                            .font(font_id.clone())
+                            .weak(),
+                    );
+                    match &mut self.editing_line {
+                        Some((editing, buffer)) if *editing == line => {
+                            let response = ime::show(
+                                ui,
+                                egui::TextEdit::singleline(buffer)
+                                    .font(font_id.clone())
+                                    .desired_width(f32::INFINITY),
+                            )
+                            .response
+                            .accessible_name(tr_args("editor-line", &[("line", &(line + 1))]));
+                            if !response.has_focus() && !response.lost_focus() {
+                                response.request_focus();
+                            }
+                            has_focus |= response.has_focus();
+                            if response.lost_focus() {
+                                commit_line = Some((line, buffer.clone()));
+                            }
+                        }
+                        _ => {
+                            // 只高亮可见的行, 跨行的状态 (如块注释) 会被忽略
+                            let range = self.line_range(line);
+                            let job = highlight(ui.ctx(), &theme, &self.text[range], self.language);
+                            let label = egui::Label::new(job)
+                                .wrap(false)
+                                .sense(egui::Sense::click());
+                            if ui.add(label).clicked() {
+                                start_editing = Some(line);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        self.has_focus = has_focus;
+        if let Some((line, buffer)) = commit_line {
+            self.editing_line = None;
+            let range = self.line_range(line);
+            if self.text[range.clone()] != buffer {
+                self.text.replace_range(range, &buffer);
+                self.record_edit(now, false);
+            }
+        }
+        if let Some(line) = start_editing {
+            let range = self.line_range(line);
+            self.editing_line = Some((line, self.text[range.clone()].to_owned()));
+            self.set_cursor(self.char_index(range.start));
+        }
+    }
+
+    fn status_ui(&self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.weak(tr_args(
+                "editor-position",
+                &[("line", &self.line), ("column", &self.column)],
+            ));
+            ui.separator();
+            ui.weak(tr_plural("editor-lines", self.line_count() as i64, &[]));
+            if self.is_large() {
+                ui.separator();
+                ui.weak(tr("editor-large-file"))
+                    .on_hover_text(tr("editor-large-file-hint"));
+            }
+            if let Some(path) = &self.path {
+                ui.separator();
+                let modified = if self.is_modified() { " ●" } else { "" };
+                ui.weak(format!("{}{modified}", path.display()));
+            }
+        });
+    }
+}
+
+// 在 TextEdit 左侧绘制行号, 自动换行的行只在第一段显示行号
+fn paint_line_numbers(
+    ui: &Ui,
+    output: &egui::text_edit::TextEditOutput,
+    right: f32,
+    font_id: &egui::FontId,
+) {
+    let painter = ui.painter();
+    let clip = ui.clip_rect();
+    let color = ui.visuals().weak_text_color();
+    let mut line = 1;
+    let mut line_start = true;
+    for row in &output.galley.rows {
+        let y = output.text_draw_pos.y + row.rect.min.y;
+        if line_start && y <= clip.max.y && y + row.rect.height() >= clip.min.y {
+            painter.text(
+                egui::pos2(right, y),
+                Align2::RIGHT_TOP,
+                line.to_string(),
+                font_id.clone(),
+                color,
+            );
+        }
+        if line_start {
+            line += 1;
+        }
+        line_start = row.ends_with_newline;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff_keeps_char_boundaries() {
+        let change = Change::diff("你好世界", "你们世界").unwrap();
+        assert_eq!(change.start, "你".len());
+        assert_eq!(change.removed, "好");
+        assert_eq!(change.inserted, "们");
+        assert_eq!(Change::diff("abc", "abc"), None);
+    }
+
+    #[test]
+    fn coalesced_edits_undo_together() {
+        let mut editor = Editor::new("test", "hello");
+        for (now, text) in [
+            (0.0, "hello w"),
+            (0.2, "hello wo"),
+            (0.4, "hello w"),
+            (0.6, "hello wx"),
+        ] {
+            editor.text = text.to_owned();
+            editor.record_edit(now, true);
+        }
+        // 超过合并时间, 单独成步
+        editor.text = "hello wx!".to_owned();
+        editor.record_edit(5.0, true);
+        assert_eq!(editor.undo.len(), 2);
+
+        editor.undo();
+        assert_eq!(editor.text(), "hello wx");
+        editor.undo();
+        assert_eq!(editor.text(), "hello");
+        assert!(!editor.can_undo());
+        editor.redo();
+        editor.redo();
+        assert_eq!(editor.text(), "hello wx!");
+    }
+
+    #[test]
+    fn merge_overlapping_changes() {
+        let mut editor = Editor::new("test", "one two three");
+        editor.text = "one 2 three".to_owned();
+        editor.record_edit(0.0, true);
+        editor.text = "1 2 three".to_owned();
+        editor.record_edit(0.1, true);
+        editor.text = "1 2 3".to_owned();
+        editor.record_edit(0.2, true);
+        assert_eq!(editor.undo.len(), 1);
+        editor.undo();
+        assert_eq!(editor.text(), "one two three");
+    }
+}
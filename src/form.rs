@@ -0,0 +1,436 @@
+use std::ops::RangeInclusive;
+
+use egui::{Id, Key, RichText, Ui};
+use regex::Regex;
+
+use crate::i18n::{tr, tr_args};
+use crate::ime;
+
+// 字段当前值的只读视图, 供校验器使用
+#[derive(Clone, Copy, Debug)]
+pub enum FieldValue<'a> {
+    Text(&'a str),
+    Number(f64),
+}
+
+// 表单字段绑定的外部数据
+pub enum Binding<'a> {
+    Text(&'a mut String),
+    U32(&'a mut u32),
+    F64(&'a mut f64),
+}
+
+impl Binding<'_> {
+    pub fn value(&self) -> FieldValue<'_> {
+        match self {
+            Binding::Text(text) => FieldValue::Text(text),
+            Binding::U32(value) => FieldValue::Number(**value as f64),
+            Binding::F64(value) => FieldValue::Number(**value),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Validator {
+    Required,
+    // 字符数范围
+    Length { min: usize, max: usize },
+    // 数值范围, 文本会先解析为数字
+    Range { min: f64, max: f64 },
+    Numeric,
+    Pattern { regex: Regex, message: String },
+}
+
+impl Validator {
+    pub fn length(min: usize, max: usize) -> Self {
+        Validator::Length { min, max }
+    }
+
+    pub fn range(range: RangeInclusive<f64>) -> Self {
+        Validator::Range {
+            min: *range.start(),
+            max: *range.end(),
+        }
+    }
+
+    // 正则无效时直接 panic, 表单定义属于代码而不是用户输入
+    pub fn pattern(pattern: &str, message: impl Into<String>) -> Self {
+        Validator::Pattern {
+            regex: Regex::new(pattern).expect("invalid validator pattern"),
+            message: message.into(),
+        }
+    }
+
+    pub fn validate(&self, value: FieldValue<'_>) -> Result<(), String> {
+        match (self, value) {
+            (Validator::Required, FieldValue::Text(text)) if text.trim().is_empty() => {
+                Err(tr("validation-required").to_owned())
+            }
+            (Validator::Required, _) => Ok(()),
+            (Validator::Length { min, max }, FieldValue::Text(text)) => {
+                let len = text.chars().count();
+                if len < *min {
+                    Err(tr_args("validation-min-length", &[("min", min)]))
+                } else if len > *max {
+                    Err(tr_args("validation-max-length", &[("max", max)]))
+                } else {
+                    Ok(())
+                }
+            }
+            (Validator::Length { .. }, FieldValue::Number(_)) => Ok(()),
+            (Validator::Range { min, max }, value) => {
+                let number = match value {
+                    FieldValue::Number(number) => number,
+                    FieldValue::Text(text) => text
+                        .trim()
+                        .parse::<f64>()
+                        .map_err(|_| tr("validation-number").to_owned())?,
+                };
+                if number < *min || number > *max {
+                    Err(tr_args("validation-range", &[("min", min), ("max", max)]))
+                } else {
+                    Ok(())
+                }
+            }
+            (Validator::Numeric, FieldValue::Text(text)) => text
+                .trim()
+                .parse::<f64>()
+                .map(|_| ())
+                .map_err(|_| tr("validation-number").to_owned()),
+            (Validator::Numeric, FieldValue::Number(_)) => Ok(()),
+            (Validator::Pattern { regex, message }, FieldValue::Text(text)) => {
+                if regex.is_match(text) {
+                    Ok(())
+                } else {
+                    Err(tr(message).to_owned())
+                }
+            }
+            (Validator::Pattern { .. }, FieldValue::Number(_)) => Ok(()),
+        }
+    }
+}
+
+pub enum FieldKind {
+    Text { hint: String },
+    Slider { range: RangeInclusive<f64> },
+}
+
+// label、hint 和校验信息都是翻译 key, 没有翻译时原样显示
+pub struct Field {
+    key: String,
+    label: String,
+    kind: FieldKind,
+    validators: Vec<Validator>,
+}
+
+impl Field {
+    pub fn text(key: impl Into<String>, label: impl Into<String>) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            kind: FieldKind::Text {
+                hint: String::new(),
+            },
+            validators: Vec::new(),
+        }
+    }
+
+    pub fn slider(
+        key: impl Into<String>,
+        label: impl Into<String>,
+        range: RangeInclusive<f64>,
+    ) -> Self {
+        Self {
+            key: key.into(),
+            label: label.into(),
+            kind: FieldKind::Slider { range },
+            validators: Vec::new(),
+        }
+    }
+
+    pub fn hint(mut self, hint: impl Into<String>) -> Self {
+        if let FieldKind::Text { hint: h } = &mut self.kind {
+            *h = hint.into();
+        }
+        self
+    }
+
+    pub fn validator(mut self, validator: Validator) -> Self {
+        self.validators.push(validator);
+        self
+    }
+
+    // 返回第一个校验失败的信息
+    pub fn validate(&self, value: FieldValue<'_>) -> Result<(), String> {
+        self.validators
+            .iter()
+            .try_for_each(|validator| validator.validate(value))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FormResponse {
+    pub changed: bool,
+    pub submitted: bool,
+}
+
+// 声明式表单: 字段定义 + 校验状态, 数据由调用方通过 Binding 提供
+pub struct Form {
+    id: Id,
+    fields: Vec<Field>,
+    errors: Vec<Option<String>>,
+    // 用户编辑过或离开过的字段才显示错误
+    touched: Vec<bool>,
+    submit_label: String,
+}
+
+impl Form {
+    pub fn new(id_source: impl std::hash::Hash) -> Self {
+        Self {
+            id: Id::new(id_source),
+            fields: Vec::new(),
+            errors: Vec::new(),
+            touched: Vec::new(),
+            submit_label: "submit".to_owned(),
+        }
+    }
+
+    pub fn field(mut self, field: Field) -> Self {
+        self.fields.push(field);
+        self.errors.push(None);
+        self.touched.push(false);
+        self
+    }
+
+    pub fn submit_label(mut self, label: impl Into<String>) -> Self {
+        self.submit_label = label.into();
+        self
+    }
+
+    fn index(&self, key: &str) -> Option<usize> {
+        self.fields.iter().position(|field| field.key == key)
+    }
+
+    fn field_id(&self, index: usize) -> Id {
+        self.id.with(("field", index))
+    }
+
+    // 字段在表单外被修改时调用, 使其错误可见
+    pub fn touch(&mut self, key: &str) {
+        if let Some(index) = self.index(key) {
+            self.touched[index] = true;
+        }
+    }
+
+    pub fn error(&self, key: &str) -> Option<&str> {
+        self.errors[self.index(key)?].as_deref()
+    }
+
+    // 校验所有字段并显示全部错误, 返回是否通过
+    pub fn validate_all(&mut self, values: &[FieldValue<'_>]) -> bool {
+        self.update_errors(values);
+        self.touched.iter_mut().for_each(|t| *t = true);
+        self.is_valid()
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.iter().all(Option::is_none)
+    }
+
+    fn update_errors(&mut self, values: &[FieldValue<'_>]) {
+        for ((field, error), value) in self.fields.iter().zip(&mut self.errors).zip(values) {
+            *error = field.validate(*value).err();
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, bindings: &mut [Binding<'_>]) -> FormResponse {
+        assert_eq!(
+            bindings.len(),
+            self.fields.len(),
+            "every form field needs a binding"
+        );
+        let mut response = FormResponse::default();
+        let mut focus_next = None;
+        let mut widget_ids = Vec::with_capacity(self.fields.len());
+
+        egui::Grid::new(self.id.with("grid"))
+            .num_columns(2)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                for (index, (field, binding)) in
+                    self.fields.iter().zip(bindings.iter_mut()).enumerate()
+                {
+                    let label = ui.label(tr(&field.label));
+                    let id = self.field_id(index);
+                    let show_error = self.touched[index] && self.errors[index].is_some();
+                    ui.vertical(|ui| {
+                        if show_error {
+                            ui.visuals_mut().extreme_bg_color =
+                                ui.visuals().error_fg_color.gamma_multiply(0.15);
+                        }
+                        let widget = field_widget(ui, id, label.id, &field.kind, binding);
+                        widget_ids.push(widget.id);
+                        if widget.changed() {
+                            response.changed = true;
+                            self.touched[index] = true;
+                        }
+                        if widget.lost_focus() {
+                            self.touched[index] = true;
+                            // Enter 跳到下一个字段, Tab 使用 egui 默认的顺序
+                            if ui.input(|i| i.key_pressed(Key::Enter)) {
+                                focus_next = Some(index + 1);
+                            }
+                        }
+                        if let (true, Some(error)) = (show_error, &self.errors[index]) {
+                            ui.label(
+                                RichText::new(error)
+                                    .small()
+                                    .color(ui.visuals().error_fg_color),
+                            );
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        let mut submitted = ui.button(tr(&self.submit_label)).clicked();
+        match focus_next {
+            Some(index) if index < widget_ids.len() => {
+                let id = widget_ids[index];
+                ui.memory_mut(|m| m.request_focus(id));
+            }
+            // 最后一个字段按 Enter 提交
+            Some(_) => submitted = true,
+            None => {}
+        }
+
+        let values: Vec<FieldValue<'_>> = bindings.iter().map(Binding::value).collect();
+        if submitted {
+            response.submitted = self.validate_all(&values);
+        } else {
+            self.update_errors(&values);
+        }
+        response
+    }
+}
+
+// label 是字段标签的 id, 读屏软件用它朗读控件的名称
+fn field_widget(
+    ui: &mut Ui,
+    id: Id,
+    label: Id,
+    kind: &FieldKind,
+    binding: &mut Binding<'_>,
+) -> egui::Response {
+    let response = match (kind, binding) {
+        (FieldKind::Text { hint }, Binding::Text(text)) => {
+            ime::show(
+                ui,
+                egui::TextEdit::singleline(*text).id(id).hint_text(tr(hint)),
+            )
+            .response
+        }
+        (FieldKind::Slider { .. }, Binding::Text(text)) => {
+            ime::show(ui, egui::TextEdit::singleline(*text).id(id)).response
+        }
+        (FieldKind::Slider { range }, Binding::U32(value)) => {
+            let range = (*range.start() as u32)..=(*range.end() as u32);
+            slider(ui, *value, range, label)
+        }
+        (FieldKind::Slider { range }, Binding::F64(value)) => {
+            slider(ui, *value, range.clone(), label)
+        }
+        (FieldKind::Text { .. }, Binding::U32(value)) => ui.add(egui::DragValue::new(*value)),
+        (FieldKind::Text { .. }, Binding::F64(value)) => ui.add(egui::DragValue::new(*value)),
+    };
+    response.labelled_by(label)
+}
+
+// 滑块自带的数值框无法设置标签, 这里关闭它并单独添加一个
+fn slider<T: egui::emath::Numeric>(
+    ui: &mut Ui,
+    value: &mut T,
+    range: RangeInclusive<T>,
+    label: Id,
+) -> egui::Response {
+    ui.horizontal(|ui| {
+        let slider = ui.add(egui::Slider::new(value, range.clone()).show_value(false));
+        let drag = ui.add(egui::DragValue::new(value).clamp_range(range));
+        slider | drag.labelled_by(label)
+    })
+    .inner
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(validator: &Validator, text: &str) -> Result<(), String> {
+        validator.validate(FieldValue::Text(text))
+    }
+
+    #[test]
+    fn required() {
+        assert!(text(&Validator::Required, "").is_err());
+        assert!(text(&Validator::Required, "  \t").is_err());
+        assert!(text(&Validator::Required, "张三").is_ok());
+        assert!(Validator::Required
+            .validate(FieldValue::Number(0.0))
+            .is_ok());
+    }
+
+    #[test]
+    fn length_counts_chars_not_bytes() {
+        let length = Validator::length(2, 4);
+        // 4 个字符, 12 个字节
+        assert!(text(&length, "你好世界").is_ok());
+        assert!(text(&length, "你").is_err());
+        assert!(text(&length, "你好世界啊").is_err());
+        assert_eq!(
+            text(&length, "你"),
+            Err(tr_args("validation-min-length", &[("min", &2)]))
+        );
+        assert_eq!(
+            text(&length, "你好世界啊"),
+            Err(tr_args("validation-max-length", &[("max", &4)]))
+        );
+    }
+
+    #[test]
+    fn range() {
+        let range = Validator::range(0.0..=150.0);
+        assert!(range.validate(FieldValue::Number(0.0)).is_ok());
+        assert!(range.validate(FieldValue::Number(150.0)).is_ok());
+        assert!(range.validate(FieldValue::Number(150.5)).is_err());
+        assert!(range.validate(FieldValue::Number(-1.0)).is_err());
+        assert!(text(&range, " 42 ").is_ok());
+        assert_eq!(text(&range, "abc"), Err(tr("validation-number").to_owned()));
+    }
+
+    #[test]
+    fn numeric_rejects_empty_and_non_numeric() {
+        let numeric = Validator::Numeric;
+        assert!(text(&numeric, "3.5").is_ok());
+        assert!(text(&numeric, "").is_err());
+        assert!(text(&numeric, "   ").is_err());
+        assert!(text(&numeric, "12abc").is_err());
+        assert!(text(&numeric, "十二").is_err());
+        assert!(numeric.validate(FieldValue::Number(1.0)).is_ok());
+    }
+
+    #[test]
+    fn pattern() {
+        let pattern = Validator::pattern(r"^[\p{L} .'-]+$", "validation-name-pattern");
+        assert!(text(&pattern, "王 小明").is_ok());
+        assert_eq!(
+            text(&pattern, "R2-D2"),
+            Err(tr("validation-name-pattern").to_owned())
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid validator pattern")]
+    fn invalid_pattern_panics() {
+        Validator::pattern("(unclosed", "validation-name-pattern");
+    }
+}
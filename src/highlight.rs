@@ -0,0 +1,630 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+use egui::text::{LayoutJob, LayoutSection, TextFormat};
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui::{Color32, FontId, Stroke, TextStyle};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Language {
+    #[default]
+    Plain,
+    Rust,
+    Toml,
+    Json,
+    Markdown,
+}
+
+impl Language {
+    pub const ALL: [Language; 5] = [
+        Language::Plain,
+        Language::Rust,
+        Language::Toml,
+        Language::Json,
+        Language::Markdown,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::Plain => "Plain text",
+            Language::Rust => "Rust",
+            Language::Toml => "TOML",
+            Language::Json => "JSON",
+            Language::Markdown => "Markdown",
+        }
+    }
+
+    // 根据扩展名识别语言, 也接受代码块的语言标记 (如 ```rust)
+    pub fn from_extension(ext: &str) -> Self {
+        match ext.to_ascii_lowercase().as_str() {
+            "rs" | "rust" => Language::Rust,
+            "toml" => Language::Toml,
+            "json" => Language::Json,
+            "md" | "markdown" => Language::Markdown,
+            _ => Language::Plain,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Self {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .map_or(Language::Plain, Language::from_extension)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum TokenType {
+    Plain,
+    Comment,
+    Keyword,
+    Literal,
+    Numeric,
+    Type,
+    Punctuation,
+    Heading,
+    Emphasis,
+    Code,
+    Link,
+}
+
+// 跨行的词法状态, 例如块注释、多行字符串和代码块
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum LineState {
+    Normal,
+    BlockComment,
+    MultiLineString(&'static str),
+    CodeFence,
+}
+
+// 跟随亮色/暗色主题的配色
+#[derive(Clone, Debug, PartialEq, Hash)]
+pub struct CodeTheme {
+    dark_mode: bool,
+    font_id: FontId,
+}
+
+impl CodeTheme {
+    pub fn from_style(style: &egui::Style) -> Self {
+        Self {
+            dark_mode: style.visuals.dark_mode,
+            font_id: TextStyle::Monospace.resolve(style),
+        }
+    }
+
+    fn format(&self, token: TokenType) -> TextFormat {
+        let color = |dark: (u8, u8, u8), light: (u8, u8, u8)| {
+            let (r, g, b) = if self.dark_mode { dark } else { light };
+            Color32::from_rgb(r, g, b)
+        };
+        let mut format =
+            TextFormat::simple(self.font_id.clone(), color((200, 200, 200), (60, 60, 60)));
+        match token {
+            TokenType::Plain => {}
+            TokenType::Comment => format.color = color((110, 150, 100), (100, 130, 90)),
+            TokenType::Keyword => format.color = color((220, 120, 200), (170, 40, 150)),
+            TokenType::Literal => format.color = color((210, 160, 110), (170, 80, 30)),
+            TokenType::Numeric => format.color = color((180, 210, 150), (40, 130, 80)),
+            TokenType::Type => format.color = color((80, 190, 200), (20, 110, 160)),
+            TokenType::Punctuation => format.color = color((150, 150, 150), (110, 110, 110)),
+            TokenType::Heading => format.color = color((240, 200, 90), (160, 100, 0)),
+            TokenType::Emphasis => {
+                format.color = color((230, 230, 230), (20, 20, 20));
+                format.italics = true;
+            }
+            TokenType::Code => {
+                format.color = color((210, 160, 110), (170, 80, 30));
+                format.background = color((40, 40, 40), (235, 235, 235));
+            }
+            TokenType::Link => {
+                format.color = color((90, 170, 255), (0, 90, 200));
+                format.underline = Stroke::new(1.0, format.color);
+            }
+        }
+        format
+    }
+}
+
+type Tokens = Vec<(Range<usize>, TokenType)>;
+
+// 逐字节扫描一行, 相邻同类 token 会合并
+struct Scanner<'a> {
+    line: &'a str,
+    pos: usize,
+    tokens: Tokens,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(line: &'a str) -> Self {
+        Self {
+            line,
+            pos: 0,
+            tokens: Vec::new(),
+        }
+    }
+
+    fn rest(&self) -> &'a str {
+        &self.line[self.pos..]
+    }
+
+    fn done(&self) -> bool {
+        self.pos >= self.line.len()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    fn push(&mut self, len: usize, token: TokenType) {
+        let mut len = len.max(1).min(self.line.len() - self.pos);
+        while !self.line.is_char_boundary(self.pos + len) {
+            len += 1;
+        }
+        let range = self.pos..self.pos + len;
+        self.pos += len;
+        if let Some((last, last_token)) = self.tokens.last_mut() {
+            if *last_token == token && last.end == range.start {
+                last.end = range.end;
+                return;
+            }
+        }
+        self.tokens.push((range, token));
+    }
+
+    fn push_rest(&mut self, token: TokenType) {
+        if !self.done() {
+            self.push(self.line.len() - self.pos, token);
+        }
+    }
+
+    fn take_while(&self, f: impl Fn(char) -> bool) -> usize {
+        self.rest()
+            .char_indices()
+            .find(|(_, c)| !f(*c))
+            .map_or(self.rest().len(), |(i, _)| i)
+    }
+
+    fn whitespace(&mut self) -> bool {
+        let len = self.take_while(char::is_whitespace);
+        if len > 0 {
+            self.push(len, TokenType::Plain);
+        }
+        len > 0
+    }
+
+    // 扫描到未转义的结束符, 返回是否找到
+    fn string_until(&mut self, delimiter: &str, token: TokenType) -> bool {
+        let rest = self.rest();
+        let mut escaped = false;
+        for (i, c) in rest.char_indices() {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if rest[i..].starts_with(delimiter) {
+                self.push(i + delimiter.len(), token);
+                return true;
+            }
+        }
+        self.push_rest(token);
+        false
+    }
+
+    fn number(&mut self) {
+        let len = self.take_while(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.');
+        self.push(len, TokenType::Numeric);
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+fn tokenize_rust(line: &str, mut state: LineState) -> (Tokens, LineState) {
+    let mut s = Scanner::new(line);
+    while !s.done() {
+        match state {
+            LineState::BlockComment => match s.rest().find("*/") {
+                Some(end) => {
+                    s.push(end + 2, TokenType::Comment);
+                    state = LineState::Normal;
+                }
+                None => s.push_rest(TokenType::Comment),
+            },
+            LineState::MultiLineString(delimiter) => {
+                if s.string_until(delimiter, TokenType::Literal) {
+                    state = LineState::Normal;
+                }
+            }
+            _ => {
+                if s.whitespace() {
+                    continue;
+                }
+                let rest = s.rest();
+                let c = s.peek().unwrap_or_default();
+                if rest.starts_with("//") {
+                    s.push_rest(TokenType::Comment);
+                } else if rest.starts_with("/*") {
+                    s.push(2, TokenType::Comment);
+                    state = LineState::BlockComment;
+                } else if c == '"' {
+                    s.push(1, TokenType::Literal);
+                    if !s.string_until("\"", TokenType::Literal) {
+                        state = LineState::MultiLineString("\"");
+                    }
+                } else if c == '\'' {
+                    // 字符字面量或生命周期
+                    let mut chars = rest.char_indices().skip(1);
+                    match (chars.next(), chars.next()) {
+                        (Some((_, '\\')), _) => {
+                            s.push(1, TokenType::Literal);
+                            s.string_until("'", TokenType::Literal);
+                        }
+                        (Some(_), Some((end, '\''))) => s.push(end + 1, TokenType::Literal),
+                        _ => {
+                            let len = 1 + rest[1..]
+                                .char_indices()
+                                .find(|(_, c)| !is_ident(*c))
+                                .map_or(rest.len() - 1, |(i, _)| i);
+                            s.push(len, TokenType::Keyword);
+                        }
+                    }
+                } else if c.is_ascii_digit() {
+                    s.number();
+                } else if is_ident(c) {
+                    let len = s.take_while(is_ident);
+                    let word = &rest[..len];
+                    let token = if RUST_KEYWORDS.contains(&word) {
+                        TokenType::Keyword
+                    } else if word.starts_with(char::is_uppercase) {
+                        TokenType::Type
+                    } else if rest[len..].starts_with('!') {
+                        // 宏
+                        TokenType::Keyword
+                    } else {
+                        TokenType::Plain
+                    };
+                    s.push(len, token);
+                } else {
+                    s.push(c.len_utf8(), TokenType::Punctuation);
+                }
+            }
+        }
+    }
+    (s.tokens, state)
+}
+
+fn tokenize_toml(line: &str, mut state: LineState) -> (Tokens, LineState) {
+    let mut s = Scanner::new(line);
+    if let LineState::MultiLineString(delimiter) = state {
+        if s.string_until(delimiter, TokenType::Literal) {
+            state = LineState::Normal;
+        }
+    }
+    if state == LineState::Normal && line.trim_start().starts_with('[') {
+        s.whitespace();
+        let len = s.rest().find('#').unwrap_or(s.rest().len());
+        s.push(len, TokenType::Type);
+    }
+    while !s.done() {
+        if let LineState::MultiLineString(_) = state {
+            break;
+        }
+        if s.whitespace() {
+            continue;
+        }
+        let rest = s.rest();
+        let c = s.peek().unwrap_or_default();
+        if c == '#' {
+            s.push_rest(TokenType::Comment);
+        } else if rest.starts_with("\"\"\"") || rest.starts_with("'''") {
+            let delimiter = if c == '"' { "\"\"\"" } else { "'''" };
+            s.push(3, TokenType::Literal);
+            if !s.string_until(delimiter, TokenType::Literal) {
+                state = LineState::MultiLineString(delimiter);
+            }
+        } else if c == '"' {
+            s.push(1, TokenType::Literal);
+            s.string_until("\"", TokenType::Literal);
+        } else if c == '\'' {
+            // 字面量字符串不处理转义
+            let len = rest[1..].find('\'').map_or(rest.len(), |i| i + 2);
+            s.push(len, TokenType::Literal);
+        } else if c.is_ascii_digit()
+            || ((c == '-' || c == '+') && rest[1..].starts_with(|c: char| c.is_ascii_digit()))
+        {
+            s.number();
+        } else if is_ident(c) || c == '-' {
+            let len = s.take_while(|c| is_ident(c) || c == '-' || c == '.');
+            let word = &rest[..len];
+            let token = if rest[len..].trim_start().starts_with('=') {
+                TokenType::Type
+            } else if word == "true" || word == "false" {
+                TokenType::Keyword
+            } else {
+                TokenType::Plain
+            };
+            s.push(len, token);
+        } else {
+            s.push(c.len_utf8(), TokenType::Punctuation);
+        }
+    }
+    (s.tokens, state)
+}
+
+fn tokenize_json(line: &str, state: LineState) -> (Tokens, LineState) {
+    let mut s = Scanner::new(line);
+    while !s.done() {
+        if s.whitespace() {
+            continue;
+        }
+        let rest = s.rest();
+        let c = s.peek().unwrap_or_default();
+        if c == '"' {
+            // 冒号前的字符串是键
+            let end = rest[1..]
+                .char_indices()
+                .scan(false, |escaped, (i, c)| {
+                    let close = !*escaped && c == '"';
+                    *escaped = !*escaped && c == '\\';
+                    Some((i, close))
+                })
+                .find(|(_, close)| *close)
+                .map_or(rest.len(), |(i, _)| i + 2);
+            let token = if rest[end..].trim_start().starts_with(':') {
+                TokenType::Type
+            } else {
+                TokenType::Literal
+            };
+            s.push(end, token);
+        } else if c.is_ascii_digit() || c == '-' {
+            let len = s.take_while(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+            s.push(len, TokenType::Numeric);
+        } else if is_ident(c) {
+            let len = s.take_while(is_ident);
+            let token = match &rest[..len] {
+                "true" | "false" | "null" => TokenType::Keyword,
+                _ => TokenType::Plain,
+            };
+            s.push(len, token);
+        } else {
+            s.push(c.len_utf8(), TokenType::Punctuation);
+        }
+    }
+    (s.tokens, state)
+}
+
+fn tokenize_markdown(line: &str, state: LineState) -> (Tokens, LineState) {
+    let mut s = Scanner::new(line);
+    let trimmed = line.trim_start();
+    if trimmed.starts_with("```") {
+        s.push_rest(TokenType::Punctuation);
+        let state = if state == LineState::CodeFence {
+            LineState::Normal
+        } else {
+            LineState::CodeFence
+        };
+        return (s.tokens, state);
+    }
+    if state == LineState::CodeFence {
+        s.push_rest(TokenType::Code);
+        return (s.tokens, state);
+    }
+    if trimmed.starts_with('#') {
+        s.push_rest(TokenType::Heading);
+        return (s.tokens, state);
+    }
+    if trimmed.starts_with('>') {
+        s.push_rest(TokenType::Comment);
+        return (s.tokens, state);
+    }
+
+    // 列表标记
+    s.whitespace();
+    let marker = s.take_while(|c| c.is_ascii_digit());
+    let rest = s.rest();
+    if marker > 0 && rest[marker..].starts_with(". ") {
+        s.push(marker + 1, TokenType::Punctuation);
+    } else if rest.starts_with("- ") || rest.starts_with("* ") || rest.starts_with("+ ") {
+        s.push(1, TokenType::Punctuation);
+    }
+
+    while !s.done() {
+        let rest = s.rest();
+        let c = s.peek().unwrap_or_default();
+        // 没有闭合的标记按普通文本处理
+        let closing = if c == '`' {
+            rest[1..].find('`').map(|i| (i + 2, TokenType::Code))
+        } else if rest.starts_with("**") || rest.starts_with("__") {
+            rest[2..]
+                .find(&rest[..2])
+                .map(|i| (i + 4, TokenType::Emphasis))
+        } else if (c == '*' || c == '_') && rest.len() > 1 && !rest[1..].starts_with(' ') {
+            rest[1..].find(c).map(|i| (i + 2, TokenType::Emphasis))
+        } else {
+            None
+        };
+        if let Some((len, token)) = closing {
+            s.push(len, token);
+        } else if "`*_".contains(c) {
+            s.push(1, TokenType::Plain);
+        } else if c == '[' {
+            // [text](url)
+            match rest
+                .find("](")
+                .and_then(|mid| rest[mid..].find(')').map(|end| mid + end))
+            {
+                Some(end) => s.push(end + 1, TokenType::Link),
+                None => s.push(1, TokenType::Plain),
+            }
+        } else {
+            let len = s.take_while(|c| !"`*_[".contains(c));
+            s.push(len, TokenType::Plain);
+        }
+    }
+    (s.tokens, state)
+}
+
+fn tokenize_line(language: Language, line: &str, state: LineState) -> (Tokens, LineState) {
+    match language {
+        Language::Plain => (vec![(0..line.len(), TokenType::Plain)], state),
+        Language::Rust => tokenize_rust(line, state),
+        Language::Toml => tokenize_toml(line, state),
+        Language::Json => tokenize_json(line, state),
+        Language::Markdown => tokenize_markdown(line, state),
+    }
+}
+
+// 缓存的行数超过这个值时, 丢弃最近没有用到的行
+const MAX_CACHED_LINES: usize = 10_000;
+
+type LineKey = (Language, LineState, u64);
+
+// 保存每一行的分词结果, 编辑后只重新分析内容或起始状态变化的行.
+// 所有调用者共用这个缓存, 所以不能在一次计算中丢弃其他代码块的行
+#[derive(Default)]
+struct Highlighter {
+    // 分词结果, 结束状态和最后一次使用时的计数
+    lines: HashMap<LineKey, (Tokens, LineState, u64)>,
+    generation: u64,
+}
+
+impl Highlighter {
+    fn prune(&mut self) {
+        if self.lines.len() <= MAX_CACHED_LINES {
+            return;
+        }
+        let mut used: Vec<u64> = self.lines.values().map(|entry| entry.2).collect();
+        used.sort_unstable();
+        let oldest = used[used.len() - MAX_CACHED_LINES];
+        self.lines.retain(|_, entry| entry.2 >= oldest);
+    }
+}
+
+impl ComputerMut<(&CodeTheme, &str, Language), LayoutJob> for Highlighter {
+    fn compute(&mut self, (theme, code, language): (&CodeTheme, &str, Language)) -> LayoutJob {
+        let formats: Vec<TextFormat> = [
+            TokenType::Plain,
+            TokenType::Comment,
+            TokenType::Keyword,
+            TokenType::Literal,
+            TokenType::Numeric,
+            TokenType::Type,
+            TokenType::Punctuation,
+            TokenType::Heading,
+            TokenType::Emphasis,
+            TokenType::Code,
+            TokenType::Link,
+        ]
+        .into_iter()
+        .map(|token| theme.format(token))
+        .collect();
+
+        self.generation += 1;
+        let generation = self.generation;
+        let mut job = LayoutJob {
+            text: code.to_owned(),
+            ..Default::default()
+        };
+        let mut state = LineState::Normal;
+        let mut offset = 0;
+        for line in code.split_inclusive('\n') {
+            let key = (language, state, egui::util::hash(line));
+            let entry = self.lines.entry(key).or_insert_with(|| {
+                let (tokens, end) = tokenize_line(language, line, state);
+                (tokens, end, generation)
+            });
+            entry.2 = generation;
+            for (range, token) in &entry.0 {
+                job.sections.push(LayoutSection {
+                    leading_space: 0.0,
+                    byte_range: range.start + offset..range.end + offset,
+                    format: formats[*token as usize].clone(),
+                });
+            }
+            state = entry.1;
+            offset += line.len();
+        }
+        self.prune();
+        job
+    }
+}
+
+type HighlightCache = FrameCache<LayoutJob, Highlighter>;
+
+// 高亮结果缓存在 egui 的 memory 中, 内容不变时不会重新计算
+pub fn highlight(
+    ctx: &egui::Context,
+    theme: &CodeTheme,
+    code: &str,
+    language: Language,
+) -> LayoutJob {
+    ctx.memory_mut(|mem| {
+        mem.caches
+            .cache::<HighlightCache>()
+            .get((theme, code, language))
+    })
+}
+
+// 只读的高亮代码块
+pub fn code_view_ui(ui: &mut egui::Ui, code: &str, language: Language) {
+    let theme = CodeTheme::from_style(ui.style());
+    let job = highlight(ui.ctx(), &theme, code, language);
+    ui.add(egui::Label::new(job).wrap(false));
+}
+
+// 给指定的字节范围加上背景色, 用于查找结果等叠加在高亮之上的标记
+pub(crate) fn add_background(job: &mut LayoutJob, ranges: &[Range<usize>], color: Color32) {
+    if ranges.is_empty() {
+        return;
+    }
+    let mut sections = Vec::with_capacity(job.sections.len() + ranges.len() * 2);
+    let mut ranges = ranges.iter().peekable();
+    for section in job.sections.drain(..) {
+        let mut start = section.byte_range.start;
+        let end = section.byte_range.end;
+        while start < end {
+            // 跳过已经结束的范围
+            while ranges.next_if(|r| r.end <= start).is_some() {}
+            let (split, highlighted) = match ranges.peek() {
+                Some(r) if r.start <= start => (r.end.min(end), true),
+                Some(r) => (r.start.min(end), false),
+                None => (end, false),
+            };
+            let mut format = section.format.clone();
+            if highlighted {
+                format.background = color;
+            }
+            sections.push(LayoutSection {
+                leading_space: 0.0,
+                byte_range: start..split,
+                format,
+            });
+            start = split;
+        }
+    }
+    job.sections = sections;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shared_cache_keeps_other_callers_lines() {
+        let theme = CodeTheme::from_style(&egui::Style::default());
+        let mut highlighter = Highlighter::default();
+        highlighter.compute((&theme, "fn main() {}\n", Language::Rust));
+        highlighter.compute((&theme, "[package]\n", Language::Toml));
+        assert_eq!(highlighter.lines.len(), 2);
+        highlighter.compute((&theme, "fn main() {}\n", Language::Rust));
+        assert_eq!(highlighter.lines.len(), 2);
+    }
+}
@@ -0,0 +1,320 @@
+use egui::{RichText, ScrollArea, Ui};
+
+use crate::i18n::{tr, tr_args, tr_plural};
+
+// 同一类修改在该时间内合并为一步
+const COALESCE_SECS: f64 = 1.0;
+const DEFAULT_MAX_ENTRIES: usize = 500;
+const DEFAULT_MAX_BYTES: usize = 8 * 1024 * 1024;
+
+struct Entry<T> {
+    // 翻译 key, 显示时才翻译, 切换语言后历史也会跟着变
+    label: String,
+    state: T,
+    // 记录时间 (egui 的 input.time)
+    time: f64,
+    bytes: usize,
+}
+
+// 基于快照的撤销/重做历史, entries[cursor] 是当前状态
+pub struct History<T> {
+    entries: Vec<Entry<T>>,
+    cursor: usize,
+    // 撤销、重做或跳转后的下一次修改不与之前的合并
+    coalescible: bool,
+    size_of: fn(&T) -> usize,
+    total_bytes: usize,
+    max_entries: usize,
+    max_bytes: usize,
+}
+
+impl<T> History<T> {
+    // size_of 估算一个快照占用的内存, 用于限制历史总大小
+    pub fn new(initial: T, size_of: fn(&T) -> usize) -> Self {
+        let bytes = size_of(&initial);
+        Self {
+            entries: vec![Entry {
+                label: "history-initial".to_owned(),
+                state: initial,
+                time: 0.0,
+                bytes,
+            }],
+            cursor: 0,
+            coalescible: false,
+            size_of,
+            total_bytes: bytes,
+            max_entries: DEFAULT_MAX_ENTRIES,
+            max_bytes: DEFAULT_MAX_BYTES,
+        }
+    }
+
+    pub fn max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn max_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    pub fn current(&self) -> &T {
+        &self.entries[self.cursor].state
+    }
+
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.cursor > 0
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.cursor + 1 < self.entries.len()
+    }
+
+    // 记录新状态并丢弃可重做的部分.
+    // coalesce 为 true 时, 与上一步同名且间隔较短的修改合并为一步
+    pub fn record(&mut self, label: impl Into<String>, state: T, now: f64, coalesce: bool) {
+        let label = label.into();
+        for entry in self.entries.drain(self.cursor + 1..) {
+            self.total_bytes -= entry.bytes;
+        }
+
+        let bytes = (self.size_of)(&state);
+        let top = &mut self.entries[self.cursor];
+        // 初始状态永远不会被合并掉
+        if coalesce
+            && self.coalescible
+            && self.cursor > 0
+            && top.label == label
+            && now - top.time <= COALESCE_SECS
+        {
+            self.total_bytes = self.total_bytes - top.bytes + bytes;
+            top.state = state;
+            top.time = now;
+            top.bytes = bytes;
+        } else {
+            self.entries.push(Entry {
+                label,
+                state,
+                time: now,
+                bytes,
+            });
+            self.total_bytes += bytes;
+            self.cursor = self.entries.len() - 1;
+        }
+        self.coalescible = coalesce;
+        self.trim();
+    }
+
+    // 超出数量或内存上限时丢弃最旧的记录, 至少保留当前状态
+    fn trim(&mut self) {
+        while self.entries.len() > 1
+            && (self.entries.len() > self.max_entries || self.total_bytes > self.max_bytes)
+        {
+            let entry = self.entries.remove(0);
+            self.total_bytes -= entry.bytes;
+            self.cursor -= 1;
+        }
+    }
+
+    pub fn undo(&mut self) -> Option<&T> {
+        self.jump(self.cursor.checked_sub(1)?)
+    }
+
+    pub fn redo(&mut self) -> Option<&T> {
+        self.jump(self.cursor + 1)
+    }
+
+    // 跳转到任意一步, 之后的记录保留, 直到产生新的修改
+    pub fn jump(&mut self, index: usize) -> Option<&T> {
+        if index >= self.entries.len() || index == self.cursor {
+            return None;
+        }
+        self.cursor = index;
+        self.coalescible = false;
+        Some(&self.entries[index].state)
+    }
+
+    // 历史面板, 返回被点击的记录下标
+    pub fn ui(&self, ui: &mut Ui, now: f64) -> Option<usize> {
+        let mut clicked = None;
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(
+                    self.can_undo(),
+                    egui::Button::new(format!("⟲ {}", tr("undo"))),
+                )
+                .on_hover_text("Ctrl+Z")
+                .clicked()
+            {
+                clicked = self.cursor.checked_sub(1);
+            }
+            if ui
+                .add_enabled(
+                    self.can_redo(),
+                    egui::Button::new(format!("⟳ {}", tr("redo"))),
+                )
+                .on_hover_text("Ctrl+Shift+Z")
+                .clicked()
+            {
+                clicked = Some(self.cursor + 1);
+            }
+        });
+        ui.label(
+            RichText::new(tr_plural(
+                "history-summary",
+                self.entries.len() as i64,
+                &[("size", &format!("{:.1}", self.total_bytes as f64 / 1024.0))],
+            ))
+            .weak(),
+        );
+        ui.separator();
+
+        ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                for (index, entry) in self.entries.iter().enumerate().rev() {
+                    let label = tr(&entry.label);
+                    let text = if index == 0 {
+                        label.to_owned()
+                    } else {
+                        format!("{label}  ({})", format_age(now - entry.time))
+                    };
+                    // 当前位置之后的是可重做的步骤
+                    let text = if index > self.cursor {
+                        RichText::new(text).weak()
+                    } else {
+                        RichText::new(text)
+                    };
+                    if ui.selectable_label(index == self.cursor, text).clicked() {
+                        clicked = Some(index);
+                    }
+                }
+            });
+        clicked
+    }
+}
+
+fn format_age(secs: f64) -> String {
+    let secs = secs.max(0.0) as u64;
+    let (key, value) = if secs < 60 {
+        ("age-seconds", secs)
+    } else if secs < 3600 {
+        ("age-minutes", secs / 60)
+    } else {
+        ("age-hours", secs / 3600)
+    };
+    tr_args(key, &[("value", &value)])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text_history() -> History<String> {
+        History::new(String::new(), |text| text.len())
+    }
+
+    #[test]
+    fn coalesced_typing_is_one_step() {
+        let mut history = text_history();
+        for (i, text) in ["H", "He", "Hel", "Hell", "Hello"].into_iter().enumerate() {
+            history.record("edit-typing", text.to_owned(), 1.0 + i as f64 * 0.2, true);
+        }
+        assert_eq!(history.len(), 2);
+        assert_eq!(history.current(), "Hello");
+        assert_eq!(history.total_bytes(), 5);
+        assert_eq!(history.undo().map(String::as_str), Some(""));
+        assert!(!history.can_undo());
+
+        // 间隔太久, 不同的标签或撤销之后都不再合并
+        let mut history = text_history();
+        history.record("edit-typing", "a".to_owned(), 1.0, true);
+        history.record("edit-typing", "ab".to_owned(), 3.0, true);
+        history.record("edit-paste", "abc".to_owned(), 3.1, true);
+        history.undo();
+        history.redo();
+        history.record("edit-paste", "abcd".to_owned(), 3.2, true);
+        assert_eq!(history.len(), 5);
+        // 不要求合并的修改之后也不合并
+        history.record("edit-typing", "x".to_owned(), 3.3, false);
+        history.record("edit-typing", "xy".to_owned(), 3.4, true);
+        assert_eq!(history.len(), 7);
+    }
+
+    #[test]
+    fn jump_restores_snapshot() {
+        let mut history = text_history();
+        for (i, text) in ["one", "two", "three"].into_iter().enumerate() {
+            history.record("edit", text.to_owned(), i as f64, false);
+        }
+        assert_eq!(history.jump(1).map(String::as_str), Some("one"));
+        assert_eq!(history.cursor(), 1);
+        assert!(history.can_redo());
+        assert_eq!(history.jump(1), None);
+        assert_eq!(history.jump(4), None);
+        assert_eq!(history.jump(3).map(String::as_str), Some("three"));
+        assert_eq!(history.jump(0).map(String::as_str), Some(""));
+
+        // 跳转后的新修改丢弃之后的记录
+        history.jump(2);
+        history.record("edit", "four".to_owned(), 10.0, false);
+        assert_eq!(history.len(), 4);
+        assert_eq!(history.current(), "four");
+        assert!(!history.can_redo());
+        assert_eq!(history.total_bytes(), "onetwofour".len());
+        assert_eq!(history.undo().map(String::as_str), Some("two"));
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_entry_cap() {
+        let mut history = text_history();
+        for i in 1..=DEFAULT_MAX_ENTRIES + 10 {
+            history.record("edit", i.to_string(), i as f64, false);
+        }
+        assert_eq!(history.len(), DEFAULT_MAX_ENTRIES);
+        assert_eq!(history.cursor(), DEFAULT_MAX_ENTRIES - 1);
+        assert_eq!(history.current(), &(DEFAULT_MAX_ENTRIES + 10).to_string());
+        history.jump(0);
+        assert_eq!(history.current(), "11");
+        let bytes: usize = (11..=DEFAULT_MAX_ENTRIES + 10)
+            .map(|i| i.to_string().len())
+            .sum();
+        assert_eq!(history.total_bytes(), bytes);
+    }
+
+    #[test]
+    fn evicts_oldest_beyond_byte_cap() {
+        // 快照本身就是估算的大小, 每步 1 MB
+        const MB: usize = 1024 * 1024;
+        let mut history = History::new(0, |bytes: &usize| *bytes);
+        for i in 1..=10 {
+            history.record("edit", MB + i, i as f64, false);
+        }
+        assert!(history.total_bytes() <= DEFAULT_MAX_BYTES);
+        assert_eq!(history.len(), 7);
+        history.jump(0);
+        assert_eq!(*history.current(), MB + 4);
+
+        // 单个快照超过上限时只保留当前状态
+        history.record("edit", DEFAULT_MAX_BYTES + 1, 11.0, false);
+        assert_eq!(history.len(), 1);
+        assert_eq!(*history.current(), DEFAULT_MAX_BYTES + 1);
+        assert!(!history.can_undo());
+    }
+}
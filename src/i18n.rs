@@ -0,0 +1,256 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::{OnceLock, RwLock};
+
+// 界面语言, 目录文件位于 assets/i18n/<code>.txt.
+// 只有主程序使用翻译, src/bin 下的独立示例保持英文
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, serde::Deserialize, serde::Serialize)]
+pub enum Locale {
+    English,
+    SimplifiedChinese,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::English, Locale::SimplifiedChinese];
+
+    pub fn code(self) -> &'static str {
+        match self {
+            Locale::English => "en",
+            Locale::SimplifiedChinese => "zh-CN",
+        }
+    }
+
+    // 用该语言自己的文字显示, 切换语言时不会看不懂
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Locale::English => "English",
+            Locale::SimplifiedChinese => "简体中文",
+        }
+    }
+
+    pub fn from_code(code: &str) -> Option<Self> {
+        let code = code.split(['.', '@']).next().unwrap_or(code);
+        let code = code.replace('_', "-").to_ascii_lowercase();
+        if code == "zh" || code.starts_with("zh-cn") || code.starts_with("zh-hans") {
+            Some(Locale::SimplifiedChinese)
+        } else if code == "en" || code.starts_with("en-") {
+            Some(Locale::English)
+        } else {
+            None
+        }
+    }
+
+    // 根据 LANG 等环境变量选择, 不支持的语言使用英文
+    pub fn from_env() -> Self {
+        ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find_map(|value| Self::from_code(&value))
+            .unwrap_or(Locale::English)
+    }
+
+    // 复数形式, 与 CLDR 的类别名一致
+    fn plural_category(self, count: i64) -> &'static str {
+        match self {
+            Locale::English if count == 1 => "one",
+            Locale::English | Locale::SimplifiedChinese => "other",
+        }
+    }
+
+    fn source(self) -> &'static str {
+        match self {
+            Locale::English => include_str!("../assets/i18n/en.txt"),
+            Locale::SimplifiedChinese => include_str!("../assets/i18n/zh-CN.txt"),
+        }
+    }
+}
+
+type Catalog = HashMap<&'static str, String>;
+
+// 每行 `key = value`, `#` 开头为注释, value 中的 \n 表示换行
+fn parse_catalog(source: &'static str) -> Catalog {
+    source
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let Some((key, value)) = line.split_once('=') else {
+                log::warn!("Invalid catalog line: {line}");
+                return None;
+            };
+            Some((key.trim(), value.trim().replace("\\n", "\n")))
+        })
+        .collect()
+}
+
+fn catalogs() -> &'static HashMap<Locale, Catalog> {
+    static CATALOGS: OnceLock<HashMap<Locale, Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        Locale::ALL
+            .into_iter()
+            .map(|locale| (locale, parse_catalog(locale.source())))
+            .collect()
+    })
+}
+
+static LOCALE: RwLock<Locale> = RwLock::new(Locale::English);
+
+pub fn locale() -> Locale {
+    *LOCALE.read().unwrap_or_else(|err| err.into_inner())
+}
+
+// 运行时切换语言, 下一帧起生效
+pub fn set_locale(locale: Locale) {
+    *LOCALE.write().unwrap_or_else(|err| err.into_inner()) = locale;
+}
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    catalogs()[&locale].get(key).map(String::as_str)
+}
+
+fn translate<'a>(catalogs: &'a HashMap<Locale, Catalog>, locale: Locale, key: &'a str) -> &'a str {
+    [locale, Locale::English]
+        .into_iter()
+        .find_map(|locale| catalogs.get(&locale)?.get(key))
+        .map_or(key, String::as_str)
+}
+
+// 翻译 key, 当前语言缺失时使用英文, 都没有时原样返回 key
+pub fn tr(key: &str) -> &str {
+    tr_in(locale(), key)
+}
+
+fn tr_in(locale: Locale, key: &str) -> &str {
+    translate(catalogs(), locale, key)
+}
+
+fn interpolate(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut message = message.to_owned();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{name}}}"), &value.to_string());
+    }
+    message
+}
+
+// 带参数的翻译, 消息中的 {name} 会被替换
+pub fn tr_args(key: &str, args: &[(&str, &dyn Display)]) -> String {
+    interpolate(tr(key), args)
+}
+
+// 复数: 查找 key.one / key.other 等, 并提供 {count} 参数
+pub fn tr_plural(key: &str, count: i64, args: &[(&str, &dyn Display)]) -> String {
+    tr_plural_in(locale(), key, count, args)
+}
+
+fn tr_plural_in(locale: Locale, key: &str, count: i64, args: &[(&str, &dyn Display)]) -> String {
+    let message = [locale, Locale::English]
+        .into_iter()
+        .find_map(|locale| {
+            let category = locale.plural_category(count);
+            lookup(locale, &format!("{key}.{category}"))
+                .or_else(|| lookup(locale, &format!("{key}.other")))
+        })
+        .unwrap_or(key);
+    let mut all_args: Vec<(&str, &dyn Display)> = vec![("count", &count)];
+    all_args.extend_from_slice(args);
+    interpolate(message, &all_args)
+}
+
+// 英文目录中有而 locale 中没有的 key, 复数形式只要求存在 .other
+pub fn missing_keys(locale: Locale) -> Vec<&'static str> {
+    let catalogs = catalogs();
+    let catalog = &catalogs[&locale];
+    let mut missing: Vec<&'static str> = catalogs[&Locale::English]
+        .keys()
+        .copied()
+        .filter(|key| match key.rsplit_once('.') {
+            Some((base, "one" | "other")) => {
+                !catalog.contains_key(format!("{base}.other").as_str())
+            }
+            _ => !catalog.contains_key(key),
+        })
+        .collect();
+    missing.sort_unstable();
+    missing.dedup();
+    missing
+}
+
+// 常见系统字体路径, 用于显示中文
+const CJK_FONT_PATHS: &[&str] = &[
+    "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/google-noto-cjk/NotoSansCJK-Regular.ttc",
+    "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+    "/usr/share/fonts/wenquanyi/wqy-microhei/wqy-microhei.ttc",
+    "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+    "C:\\Windows\\Fonts\\msyh.ttc",
+    "C:\\Windows\\Fonts\\simhei.ttf",
+    "C:\\Windows\\Fonts\\simsun.ttc",
+    "/System/Library/Fonts/PingFang.ttc",
+    "/System/Library/Fonts/STHeiti Light.ttc",
+    "/Library/Fonts/Arial Unicode.ttf",
+];
+
+// 查找可以显示中文的字体, EGUI_DEMO_CJK_FONT 环境变量优先
+pub(crate) fn load_cjk_font() -> Option<Vec<u8>> {
+    let custom = std::env::var("EGUI_DEMO_CJK_FONT").ok();
+    let font = custom
+        .iter()
+        .map(String::as_str)
+        .chain(CJK_FONT_PATHS.iter().copied())
+        .find_map(|path| std::fs::read(path).ok());
+    if font.is_none() {
+        log::warn!("No CJK font found, Chinese text will not render. Set EGUI_DEMO_CJK_FONT to a font file.");
+    }
+    font
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 不修改全局语言, 避免影响并行运行的其他测试
+    #[test]
+    fn chinese_catalog_is_complete() {
+        assert_eq!(missing_keys(Locale::SimplifiedChinese), Vec::<&str>::new());
+        assert_eq!(tr_in(Locale::SimplifiedChinese, "save"), "保存");
+    }
+
+    #[test]
+    fn plural_forms() {
+        let name: &dyn Display = &"Zzrk";
+        let hello = |locale, count| tr_plural_in(locale, "hello", count, &[("name", name)]);
+        assert_eq!(hello(Locale::English, 1), "Hello 'Zzrk', 1 year old");
+        assert_eq!(hello(Locale::English, 2), "Hello 'Zzrk', 2 years old");
+        assert_eq!(hello(Locale::English, 0), "Hello 'Zzrk', 0 years old");
+        // 中文没有 hello.one, 使用 hello.other
+        assert_eq!(hello(Locale::SimplifiedChinese, 1), "你好 'Zzrk', 1 岁");
+    }
+
+    #[test]
+    fn fallback() {
+        let catalogs = HashMap::from([
+            (Locale::English, parse_catalog("save = Save\nquit = Quit")),
+            (Locale::SimplifiedChinese, parse_catalog("save = 保存")),
+        ]);
+        assert_eq!(
+            translate(&catalogs, Locale::SimplifiedChinese, "save"),
+            "保存"
+        );
+        // 中文目录缺少的 key 使用英文
+        assert_eq!(
+            translate(&catalogs, Locale::SimplifiedChinese, "quit"),
+            "Quit"
+        );
+        assert_eq!(translate(&catalogs, Locale::English, "none"), "none");
+        assert_eq!(
+            tr_in(Locale::SimplifiedChinese, "no-such-key"),
+            "no-such-key"
+        );
+        assert_eq!(
+            tr_plural_in(Locale::SimplifiedChinese, "no-such-key", 3, &[]),
+            "no-such-key"
+        );
+        assert_eq!(parse_catalog("# comment\n a = b\\nc ")["a"], "b\nc");
+    }
+}
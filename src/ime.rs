@@ -0,0 +1,206 @@
+use std::ops::Range;
+
+use egui::text_edit::TextEditOutput;
+use egui::{Context, Event, FullOutput, Id, RawInput, Stroke, Ui};
+
+// 正在组字 (preedit) 的文本框, 同一时间只有获得焦点的那个
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Preedit {
+    pub id: Id,
+    // 未上屏文字在文本中的字符范围
+    pub range: Range<usize>,
+    frame: u64,
+}
+
+fn preedit_id() -> Id {
+    Id::new("ime_preedit")
+}
+
+// 当前的组字状态, 组字中的文本框不再显示时自动失效
+pub fn preedit(ctx: &Context) -> Option<Preedit> {
+    ctx.data(|d| d.get_temp::<Preedit>(preedit_id()))
+        .filter(|preedit| preedit.frame + 1 >= ctx.frame_nr())
+}
+
+pub fn is_composing(ctx: &Context, id: Id) -> bool {
+    preedit(ctx).is_some_and(|preedit| preedit.id == id)
+}
+
+// 代替 TextEdit::show, 支持输入法组字
+pub fn show(ui: &mut Ui, edit: egui::TextEdit<'_>) -> TextEditOutput {
+    let output = edit.show(ui);
+    update(ui, &output);
+    output
+}
+
+// egui 把未上屏的文字当作选区显示, 这里再给它加上下划线,
+// 并把候选窗口固定在组字开始的位置, 不随输入左右移动
+pub fn update(ui: &Ui, output: &TextEditOutput) {
+    let ctx = ui.ctx();
+    let id = output.response.id;
+    let mut composing = is_composing(ctx, id);
+    if output.response.has_focus() {
+        ui.input(|i| {
+            for event in &i.events {
+                match event {
+                    Event::CompositionStart => composing = true,
+                    Event::CompositionEnd(_) => composing = false,
+                    _ => {}
+                }
+            }
+        });
+    } else {
+        composing = false;
+    }
+
+    let Some(cursor_range) = output.cursor_range.filter(|_| composing) else {
+        if is_composing(ctx, id) {
+            ctx.data_mut(|d| d.remove::<Preedit>(preedit_id()));
+        }
+        return;
+    };
+    let preedit = Preedit {
+        id,
+        range: cursor_range.as_sorted_char_range(),
+        frame: ctx.frame_nr(),
+    };
+    ctx.data_mut(|d| d.insert_temp(preedit_id(), preedit));
+
+    let galley = &output.galley;
+    let offset = output.text_draw_pos.to_vec2();
+    let [start, end] = cursor_range.sorted_cursors();
+    let start_rect = galley.pos_from_cursor(&start).translate(offset);
+    ctx.output_mut(|o| {
+        // 与 TextEdit 一致: web 上使用光标顶部, 原生窗口使用底部
+        o.text_cursor_pos = Some(if cfg!(target_arch = "wasm32") {
+            start_rect.left_top()
+        } else {
+            start_rect.left_bottom()
+        });
+    });
+
+    let painter = ui.painter().with_clip_rect(output.text_clip_rect);
+    let stroke = Stroke::new(1.0, ui.visuals().text_color());
+    // 自动换行时未上屏的文字可能跨行
+    for row_index in start.rcursor.row..=end.rcursor.row {
+        let Some(row) = galley.rows.get(row_index) else {
+            break;
+        };
+        let left = if row_index == start.rcursor.row {
+            galley.pos_from_cursor(&start).min.x
+        } else {
+            row.rect.min.x
+        };
+        let right = if row_index == end.rcursor.row {
+            galley.pos_from_cursor(&end).min.x
+        } else {
+            row.rect.max.x
+        };
+        if right > left {
+            let y = row.rect.max.y - 1.0;
+            painter.line_segment(
+                [egui::pos2(left, y) + offset, egui::pos2(right, y) + offset],
+                stroke,
+            );
+        }
+    }
+}
+
+// 模拟一次输入法输入: 逐个显示 preedits, 最后上屏 commit
+pub fn composition_events(preedits: &[&str], commit: &str) -> Vec<Event> {
+    let mut events = vec![Event::CompositionStart];
+    events.extend(
+        preedits
+            .iter()
+            .map(|text| Event::CompositionUpdate((*text).to_owned())),
+    );
+    events.push(Event::CompositionEnd(commit.to_owned()));
+    events
+}
+
+// 无界面地把事件逐帧交给 run_ui, 每帧一个事件, 与真实输入法一致.
+// after_frame 在每帧之后调用, 可以检查组字过程中的文本和候选窗口位置
+pub fn feed_headless(
+    ctx: &Context,
+    events: impl IntoIterator<Item = Event>,
+    mut run_ui: impl FnMut(&Context),
+    mut after_frame: impl FnMut(&Event, &FullOutput),
+) {
+    let mut time = ctx.input(|i| i.time);
+    for event in events {
+        time += 1.0 / 60.0;
+        let raw = RawInput {
+            time: Some(time),
+            events: vec![event.clone()],
+            ..Default::default()
+        };
+        let output = ctx.run(raw, &mut run_ui);
+        after_frame(&event, &output);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::TextEdit;
+
+    use super::*;
+
+    fn id() -> Id {
+        Id::new("ime test")
+    }
+
+    fn run(ctx: &Context, text: &mut String) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            show(ui, TextEdit::singleline(text).id(id()));
+        });
+    }
+
+    // 运行一帧让文本框获得焦点, 光标在文本末尾
+    fn focused(text: &mut String) -> Context {
+        let ctx = Context::default();
+        ctx.memory_mut(|mem| mem.request_focus(id()));
+        let _ = ctx.run(RawInput::default(), |ctx| run(ctx, text));
+        ctx
+    }
+
+    fn cursor(ctx: &Context) -> Option<usize> {
+        let range = TextEdit::load_state(ctx, id())?.ccursor_range()?;
+        assert_eq!(range.primary, range.secondary);
+        Some(range.primary.index)
+    }
+
+    #[test]
+    fn commit_multi_byte_text() {
+        let mut text = "ab".to_owned();
+        let ctx = focused(&mut text);
+        let mut preedits = Vec::new();
+        feed_headless(
+            &ctx,
+            composition_events(&["n", "ni", "你"], "你好"),
+            |ctx| run(ctx, &mut text),
+            |_, _| preedits.push(preedit(&ctx).map(|preedit| preedit.range)),
+        );
+        assert_eq!(text, "ab你好");
+        // 光标按字符计数, 在上屏文字之后
+        assert_eq!(cursor(&ctx), Some(4));
+        assert_eq!(preedits[2], Some(2..4));
+        assert_eq!(preedits[3], Some(2..3));
+        assert_eq!(preedits.last(), Some(&None));
+    }
+
+    #[test]
+    fn cancelled_composition_keeps_text() {
+        let mut text = "ab".to_owned();
+        let ctx = focused(&mut text);
+        // 按 Esc 取消组字时输入法先清空 preedit, 再以空文本结束
+        feed_headless(
+            &ctx,
+            composition_events(&["n", "ni", ""], ""),
+            |ctx| run(ctx, &mut text),
+            |_, _| {},
+        );
+        assert_eq!(text, "ab");
+        assert_eq!(cursor(&ctx), Some(2));
+        assert!(preedit(&ctx).is_none());
+    }
+}
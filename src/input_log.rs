@@ -0,0 +1,277 @@
+use std::collections::VecDeque;
+use std::path::Path;
+
+use egui::{Event, RawInput, RichText, ScrollArea, Ui};
+
+use crate::i18n::{tr, tr_args};
+use crate::ime;
+use crate::toast::Toasts;
+
+const DEFAULT_CAPACITY: usize = 2000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+pub enum Category {
+    Key,
+    Text,
+    Pointer,
+    PointerMove,
+    Scroll,
+    Ime,
+    Files,
+    Other,
+}
+
+impl Category {
+    pub const ALL: [Category; 8] = [
+        Category::Key,
+        Category::Text,
+        Category::Pointer,
+        Category::PointerMove,
+        Category::Scroll,
+        Category::Ime,
+        Category::Files,
+        Category::Other,
+    ];
+
+    pub fn name(self) -> &'static str {
+        tr(match self {
+            Category::Key => "input-category-keys",
+            Category::Text => "input-category-text",
+            Category::Pointer => "input-category-pointer",
+            Category::PointerMove => "input-category-pointer-moves",
+            Category::Scroll => "input-category-scroll",
+            Category::Ime => "input-category-ime",
+            Category::Files => "input-category-files",
+            Category::Other => "input-category-other",
+        })
+    }
+
+    fn of(event: &Event) -> Self {
+        match event {
+            Event::Key { .. } => Category::Key,
+            Event::Text(_) | Event::Paste(_) | Event::Copy | Event::Cut => Category::Text,
+            Event::PointerButton { .. } | Event::PointerGone | Event::Touch { .. } => {
+                Category::Pointer
+            }
+            Event::PointerMoved(_) => Category::PointerMove,
+            Event::Scroll(_) | Event::Zoom(_) => Category::Scroll,
+            Event::CompositionStart | Event::CompositionUpdate(_) | Event::CompositionEnd(_) => {
+                Category::Ime
+            }
+            #[allow(unreachable_patterns)]
+            _ => Category::Other,
+        }
+    }
+}
+
+#[derive(Clone, Debug, serde::Serialize)]
+pub struct LoggedEvent {
+    // RawInput::time, 没有时为 egui 的帧时间
+    pub time: f64,
+    pub frame: u64,
+    pub category: Category,
+    pub summary: String,
+    // 文件拖放不是 Event, 只有摘要
+    pub event: Option<Event>,
+}
+
+// 输入事件记录器, 最多保留 capacity 条
+pub struct InputLog {
+    events: VecDeque<LoggedEvent>,
+    capacity: usize,
+    frame: u64,
+    paused: bool,
+    // 勾选的类别才会被记录
+    enabled: Vec<Category>,
+    search: String,
+    // 上一帧悬停的文件数, 只在变化时记录
+    hovered_files: usize,
+}
+
+impl Default for InputLog {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            capacity: DEFAULT_CAPACITY,
+            frame: 0,
+            paused: false,
+            // 指针移动太频繁, 默认不记录
+            enabled: Category::ALL
+                .into_iter()
+                .filter(|c| *c != Category::PointerMove)
+                .collect(),
+            search: String::new(),
+            hovered_files: 0,
+        }
+    }
+}
+
+impl InputLog {
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    pub fn events(&self) -> impl Iterator<Item = &LoggedEvent> {
+        self.events.iter()
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn push(&mut self, event: LoggedEvent) {
+        if !self.enabled.contains(&event.category) {
+            return;
+        }
+        if self.events.len() == self.capacity {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    // 每帧调用一次, 传入本帧未经处理的输入
+    pub fn record(&mut self, raw: &RawInput, now: f64) {
+        self.frame += 1;
+        if self.paused {
+            return;
+        }
+        let time = raw.time.unwrap_or(now);
+        let frame = self.frame;
+        for event in &raw.events {
+            self.push(LoggedEvent {
+                time,
+                frame,
+                category: Category::of(event),
+                summary: format!("{event:?}"),
+                event: Some(event.clone()),
+            });
+        }
+        if raw.hovered_files.len() != self.hovered_files {
+            self.hovered_files = raw.hovered_files.len();
+            let names: Vec<String> = raw
+                .hovered_files
+                .iter()
+                .map(|file| match &file.path {
+                    Some(path) => path.display().to_string(),
+                    None => file.mime.clone(),
+                })
+                .collect();
+            self.push(LoggedEvent {
+                time,
+                frame,
+                category: Category::Files,
+                summary: format!("Hovered files: [{}]", names.join(", ")),
+                event: None,
+            });
+        }
+        for file in &raw.dropped_files {
+            let name = match &file.path {
+                Some(path) => path.display().to_string(),
+                None => file.name.clone(),
+            };
+            self.push(LoggedEvent {
+                time,
+                frame,
+                category: Category::Files,
+                summary: format!("Dropped file: {name}"),
+                event: None,
+            });
+        }
+    }
+
+    fn matches(&self, event: &LoggedEvent) -> bool {
+        self.search.is_empty()
+            || event
+                .summary
+                .to_lowercase()
+                .contains(&self.search.to_lowercase())
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let events: Vec<&LoggedEvent> = self.events.iter().filter(|e| self.matches(e)).collect();
+        serde_json::to_string_pretty(&events)
+    }
+
+    pub fn export(&self, path: &Path) -> std::io::Result<()> {
+        let json = self.to_json().map_err(std::io::Error::from)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            let label = if self.paused {
+                format!("▶ {}", tr("input-resume"))
+            } else {
+                format!("⏸ {}", tr("input-pause"))
+            };
+            if ui.button(label).clicked() {
+                self.paused = !self.paused;
+            }
+            if ui.button(format!("🗑 {}", tr("input-clear"))).clicked() {
+                self.clear();
+            }
+            if ui.button(format!("💾 {}", tr("input-export"))).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("input-events.json")
+                    .save_file()
+                {
+                    match self.export(&path) {
+                        Ok(()) => {
+                            toasts.success(tr_args("input-exported", &[("path", &path.display())]))
+                        }
+                        Err(err) => toasts.error(tr_args(
+                            "file-save-failed",
+                            &[("path", &path.display()), ("error", &err)],
+                        )),
+                    }
+                }
+            }
+            ui.label(RichText::new(format!("{}/{}", self.events.len(), self.capacity)).weak());
+        });
+        ui.horizontal_wrapped(|ui| {
+            ui.label(tr("input-record-label"));
+            for category in Category::ALL {
+                let mut enabled = self.enabled.contains(&category);
+                if ui.checkbox(&mut enabled, category.name()).changed() {
+                    if enabled {
+                        self.enabled.push(category);
+                    } else {
+                        self.enabled.retain(|c| *c != category);
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr("input-filter-label"));
+            ime::show(ui, egui::TextEdit::singleline(&mut self.search));
+        });
+        ui.separator();
+
+        let rows: Vec<&LoggedEvent> = self.events.iter().filter(|e| self.matches(e)).collect();
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, rows.len(), |ui, range| {
+                for event in &rows[range] {
+                    ui.monospace(format!(
+                        "{:>9.3}s #{:<6} {:<12} {}",
+                        event.time,
+                        event.frame,
+                        event.category.name(),
+                        event.summary
+                    ));
+                }
+            });
+    }
+}
@@ -1,3 +1,48 @@
+mod a11y;
 mod app;
+mod attention;
+mod dialog;
+mod editor;
+mod form;
+mod highlight;
+mod history;
+mod i18n;
+mod ime;
+mod input_log;
+mod markdown;
+mod notify;
+mod session;
+mod settings;
+mod shortcuts;
+mod task;
+mod theme;
+mod timer;
+mod toast;
+pub use a11y::{AccessTree, Accessible};
 pub use app::start_puffin_server;
 pub use app::MyApp;
+pub use attention::{AttentionSchedule, AttentionScheduler, AttentionState};
+pub use dialog::{modal_backdrop, DialogId, DialogResponse, Dialogs};
+pub use editor::Editor;
+pub use form::{Binding, Field, FieldKind, FieldValue, Form, FormResponse, Validator};
+pub use highlight::{code_view_ui, highlight, CodeTheme, Language};
+pub use history::History;
+pub use i18n::{locale, missing_keys, set_locale, tr, tr_args, tr_plural, Locale};
+pub use ime::{composition_events, feed_headless, preedit, Preedit};
+pub use input_log::{Category, InputLog, LoggedEvent};
+pub use markdown::{markdown_ui, MarkdownViewer};
+#[cfg(all(feature = "notifications", target_os = "linux"))]
+pub use notify::DbusNotifier;
+pub use notify::{Notification, NotificationEvent, Urgency};
+pub use session::{replay_headless, Player, Recorder, Session, SESSION_VERSION};
+pub use settings::{high_contrast_visuals, DisplaySettings};
+pub use shortcuts::{format_shortcut, fuzzy_match, parse_shortcut, CommandPalette, Shortcuts};
+pub use task::{
+    panic_message, CancellationToken, Cancelled, TaskContext, TaskError, TaskHandle, TaskId,
+    TaskRunner, TaskState,
+};
+pub use theme::{Palette, ThemePreference, ThemeSettings};
+pub use timer::{
+    format_remaining, request_repaint_at, Clock, Countdown, FakeClock, SystemClock, Timers,
+};
+pub use toast::{Severity, Toasts};
@@ -1,10 +1,28 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
+// 命令行参数: --replay <session.json> [--speed <倍速>]
+fn replay_args() -> Option<(std::path::PathBuf, f64)> {
+    let mut args = std::env::args().skip(1);
+    let mut path = None;
+    let mut speed = 1.0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--replay" => path = args.next().map(Into::into),
+            "--speed" => speed = args.next().and_then(|s| s.parse().ok()).unwrap_or(1.0),
+            _ => log::warn!("Unknown argument: {arg}"),
+        }
+    }
+    path.map(|path| (path, speed))
+}
+
 fn main() -> eframe::Result<()> {
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
-    egui_demo::start_puffin_server(); // NOTE: you may only want to call this if the users specifies some flag or clicks a button!
+    let replay = replay_args();
+    let puffin_result = egui_demo::start_puffin_server(); // NOTE: you may only want to call this if the users specifies some flag or clicks a button!
     let native_options = eframe::NativeOptions {
         drag_and_drop_support: true,
+        // 需要系统主题才能实现跟随系统, Linux 上默认关闭
+        follow_system_theme: true,
         initial_window_size: Some(egui::vec2(400.0, 1000.0)),
         ..Default::default()
     };
@@ -15,7 +33,19 @@ fn main() -> eframe::Result<()> {
         Box::new(|cc| {
             // egui 加载 svg 必须的 loaders
             // egui_extras::loaders::install(&cc.egui_ctx);
-            Box::new(egui_demo::MyApp::new(cc))
+            let mut app = egui_demo::MyApp::new(cc);
+            if let Err(err) = puffin_result {
+                app.toasts_mut().error(err);
+            }
+            if let Some((path, speed)) = replay {
+                match egui_demo::Session::load(&path) {
+                    Ok(session) => app.replay(session, speed),
+                    Err(err) => app
+                        .toasts_mut()
+                        .error(format!("Failed to load {}: {err}", path.display())),
+                }
+            }
+            Box::new(app)
         }),
     )
 }
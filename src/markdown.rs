@@ -0,0 +1,531 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use egui::util::cache::{ComputerMut, FrameCache};
+use egui::{RichText, TextStyle, Ui};
+use egui_extras::RetainedImage;
+use pulldown_cmark::{CodeBlockKind, CowStr, Event, HeadingLevel, Options, Parser, Tag};
+
+use crate::app::{heading2, heading3};
+use crate::highlight::{code_view_ui, Language};
+use crate::i18n::{tr, tr_args};
+use crate::toast::Toasts;
+
+// 段落内的元素
+enum Inline {
+    Text(RichText),
+    Link(RichText, String),
+    Image { url: String, alt: String },
+    LineBreak,
+}
+
+#[derive(Default)]
+struct Table {
+    head: Vec<Vec<Inline>>,
+    rows: Vec<Vec<Vec<Inline>>>,
+}
+
+// 渲染时需要的外部资源: 图片缓存和被点击的本地链接
+struct Resources<'a> {
+    base_dir: Option<&'a Path>,
+    images: &'a mut HashMap<String, Option<RetainedImage>>,
+    open_request: Option<String>,
+}
+
+impl Resources<'_> {
+    fn image(&mut self, url: &str) -> Option<&RetainedImage> {
+        let base_dir = self.base_dir;
+        self.images
+            .entry(url.to_owned())
+            .or_insert_with(|| {
+                if is_remote(url) {
+                    return None;
+                }
+                let path = base_dir.map_or_else(|| PathBuf::from(url), |dir| dir.join(url));
+                let bytes = std::fs::read(&path).ok()?;
+                RetainedImage::from_image_bytes(url, &bytes).ok()
+            })
+            .as_ref()
+    }
+}
+
+fn is_remote(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("mailto:")
+}
+
+// 把 pulldown-cmark 的事件流转换为 egui 控件
+#[derive(Default)]
+struct Renderer {
+    inlines: Vec<Inline>,
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    quote: usize,
+    heading: Option<HeadingLevel>,
+    link: Option<String>,
+    image: Option<(String, String)>,
+    code_block: Option<(String, String)>,
+    // 列表栈, 有序列表保存下一个序号
+    lists: Vec<Option<u64>>,
+    item_prefix: Option<String>,
+    table: Option<Table>,
+    table_row: Vec<Vec<Inline>>,
+    // 没有配置自定义标题样式时统一使用 Heading
+    custom_headings: bool,
+}
+
+impl Renderer {
+    fn rich(&self, text: &str) -> RichText {
+        let mut rich = RichText::new(text);
+        rich = match self.heading {
+            Some(HeadingLevel::H1) => rich.text_style(TextStyle::Heading),
+            Some(HeadingLevel::H2) if self.custom_headings => rich.text_style(heading2()),
+            Some(HeadingLevel::H3) if self.custom_headings => rich.text_style(heading3()),
+            Some(HeadingLevel::H2 | HeadingLevel::H3) => rich.text_style(TextStyle::Heading),
+            Some(_) => rich.strong(),
+            None => rich,
+        };
+        if self.strong > 0 {
+            rich = rich.strong();
+        }
+        if self.emphasis > 0 {
+            rich = rich.italics();
+        }
+        if self.strikethrough > 0 {
+            rich = rich.strikethrough();
+        }
+        if self.quote > 0 {
+            rich = rich.weak();
+        }
+        rich
+    }
+
+    fn indent(&self) -> f32 {
+        16.0 * (self.lists.len().saturating_sub(1) + self.quote) as f32
+    }
+
+    // 输出当前积累的段落
+    fn flush(&mut self, ui: &mut Ui, resources: &mut Resources<'_>) {
+        if self.inlines.is_empty() && self.item_prefix.is_none() {
+            return;
+        }
+        let inlines = std::mem::take(&mut self.inlines);
+        let prefix = self.item_prefix.take();
+        let indent = self.indent();
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing.x = 0.0;
+            ui.add_space(indent);
+            if let Some(prefix) = prefix {
+                ui.label(prefix);
+            }
+            for inline in inlines {
+                inline_ui(ui, inline, resources);
+            }
+        });
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if let Some((_, code)) = &mut self.code_block {
+            code.push_str(text);
+        } else if let Some((_, alt)) = &mut self.image {
+            alt.push_str(text);
+        } else if let Some(url) = &self.link {
+            self.inlines
+                .push(Inline::Link(self.rich(text), url.clone()));
+        } else {
+            self.inlines.push(Inline::Text(self.rich(text)));
+        }
+    }
+
+    fn start(&mut self, ui: &mut Ui, resources: &mut Resources<'_>, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => {}
+            Tag::Heading(level, _, _) => {
+                self.flush(ui, resources);
+                ui.add_space(6.0);
+                self.heading = Some(level);
+            }
+            Tag::BlockQuote => {
+                self.flush(ui, resources);
+                self.quote += 1;
+            }
+            Tag::CodeBlock(kind) => {
+                self.flush(ui, resources);
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => info
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_owned(),
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code_block = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.flush(ui, resources);
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush(ui, resources);
+                let prefix = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => "• ".to_owned(),
+                };
+                self.item_prefix = Some(prefix);
+            }
+            Tag::FootnoteDefinition(name) => {
+                self.flush(ui, resources);
+                self.inlines
+                    .push(Inline::Text(RichText::new(format!("[{name}]: ")).small()));
+            }
+            Tag::Table(_) => {
+                self.flush(ui, resources);
+                self.table = Some(Table::default());
+            }
+            Tag::TableHead | Tag::TableRow | Tag::TableCell => {}
+            Tag::Emphasis => self.emphasis += 1,
+            Tag::Strong => self.strong += 1,
+            Tag::Strikethrough => self.strikethrough += 1,
+            Tag::Link(_, url, _) => self.link = Some(url.to_string()),
+            Tag::Image(_, url, _) => self.image = Some((url.to_string(), String::new())),
+        }
+    }
+
+    fn end(&mut self, ui: &mut Ui, resources: &mut Resources<'_>, tag: Tag<'_>) {
+        match tag {
+            Tag::Paragraph => {
+                self.flush(ui, resources);
+                ui.add_space(4.0);
+            }
+            Tag::Heading(..) => {
+                self.flush(ui, resources);
+                self.heading = None;
+                ui.add_space(4.0);
+            }
+            Tag::BlockQuote => {
+                self.flush(ui, resources);
+                self.quote -= 1;
+            }
+            Tag::CodeBlock(_) => {
+                if let Some((language, code)) = self.code_block.take() {
+                    let indent = self.indent();
+                    ui.horizontal(|ui| {
+                        ui.add_space(indent);
+                        egui::Frame::none()
+                            .fill(ui.visuals().extreme_bg_color)
+                            .inner_margin(6.0)
+                            .rounding(4.0)
+                            .show(ui, |ui| {
+                                code_view_ui(
+                                    ui,
+                                    code.trim_end_matches('\n'),
+                                    Language::from_extension(&language),
+                                );
+                            });
+                    });
+                    ui.add_space(4.0);
+                }
+            }
+            Tag::List(_) => {
+                self.flush(ui, resources);
+                self.lists.pop();
+                if self.lists.is_empty() {
+                    ui.add_space(4.0);
+                }
+            }
+            Tag::Item | Tag::FootnoteDefinition(_) => self.flush(ui, resources),
+            Tag::Table(_) => {
+                if let Some(table) = self.table.take() {
+                    table_ui(ui, table, resources);
+                    ui.add_space(4.0);
+                }
+            }
+            Tag::TableHead => {
+                if let Some(table) = &mut self.table {
+                    table.head = std::mem::take(&mut self.table_row);
+                }
+            }
+            Tag::TableRow => {
+                if let Some(table) = &mut self.table {
+                    table.rows.push(std::mem::take(&mut self.table_row));
+                }
+            }
+            Tag::TableCell => {
+                self.table_row.push(std::mem::take(&mut self.inlines));
+            }
+            Tag::Emphasis => self.emphasis -= 1,
+            Tag::Strong => self.strong -= 1,
+            Tag::Strikethrough => self.strikethrough -= 1,
+            Tag::Link(..) => self.link = None,
+            Tag::Image(..) => {
+                if let Some((url, alt)) = self.image.take() {
+                    self.inlines.push(Inline::Image { url, alt });
+                }
+            }
+        }
+    }
+
+    fn event(&mut self, ui: &mut Ui, resources: &mut Resources<'_>, event: Event<'_>) {
+        match event {
+            Event::Start(tag) => self.start(ui, resources, tag),
+            Event::End(tag) => self.end(ui, resources, tag),
+            Event::Text(text) => self.push_text(&text),
+            Event::Code(code) => self
+                .inlines
+                .push(Inline::Text(RichText::new(code.as_ref()).code())),
+            Event::Html(html) => {
+                self.inlines.push(Inline::Text(
+                    RichText::new(html.trim_end()).weak().monospace(),
+                ));
+            }
+            Event::FootnoteReference(name) => {
+                self.inlines.push(Inline::Text(
+                    RichText::new(format!("[{name}]")).small_raised(),
+                ));
+            }
+            Event::SoftBreak => self.push_text(" "),
+            Event::HardBreak => self.inlines.push(Inline::LineBreak),
+            Event::Rule => {
+                self.flush(ui, resources);
+                ui.separator();
+            }
+            Event::TaskListMarker(checked) => {
+                let marker = if checked { "☑ " } else { "☐ " };
+                self.inlines.push(Inline::Text(RichText::new(marker)));
+            }
+        }
+    }
+}
+
+fn inline_ui(ui: &mut Ui, inline: Inline, resources: &mut Resources<'_>) {
+    match inline {
+        Inline::Text(text) => {
+            ui.label(text);
+        }
+        Inline::Link(text, url) => {
+            if is_remote(&url) {
+                ui.hyperlink_to(text, url);
+            } else if ui.link(text).on_hover_text(&url).clicked() {
+                resources.open_request = Some(url);
+            }
+        }
+        Inline::Image { url, alt } => {
+            let max_size = egui::vec2(ui.available_width(), 400.0);
+            match resources.image(&url) {
+                Some(image) => {
+                    image.show_max_size(ui, max_size).on_hover_text(alt);
+                }
+                None if is_remote(&url) => {
+                    ui.hyperlink_to(format!("🖼 {alt}"), url);
+                }
+                None => {
+                    ui.label(RichText::new(format!("🖼 {alt}")).weak())
+                        .on_hover_text(tr_args("markdown-image-failed", &[("url", &url)]));
+                }
+            }
+        }
+        Inline::LineBreak => ui.end_row(),
+    }
+}
+
+fn table_ui(ui: &mut Ui, table: Table, resources: &mut Resources<'_>) {
+    let id = ui.next_auto_id();
+    egui::Grid::new(id).striped(true).show(ui, |ui| {
+        for cell in table.head {
+            ui.horizontal_wrapped(|ui| {
+                ui.spacing_mut().item_spacing.x = 0.0;
+                for inline in cell {
+                    let inline = match inline {
+                        Inline::Text(text) => Inline::Text(text.strong()),
+                        other => other,
+                    };
+                    inline_ui(ui, inline, resources);
+                }
+            });
+        }
+        ui.end_row();
+        for row in table.rows {
+            for cell in row {
+                ui.horizontal_wrapped(|ui| {
+                    ui.spacing_mut().item_spacing.x = 0.0;
+                    for inline in cell {
+                        inline_ui(ui, inline, resources);
+                    }
+                });
+            }
+            ui.end_row();
+        }
+    });
+}
+
+fn owned_str(text: CowStr<'_>) -> CowStr<'static> {
+    CowStr::from(text.into_string())
+}
+
+fn owned_tag(tag: Tag<'_>) -> Tag<'static> {
+    match tag {
+        // 渲染时不用标题的 id 和 class
+        Tag::Heading(level, _, _) => Tag::Heading(level, None, Vec::new()),
+        Tag::CodeBlock(CodeBlockKind::Fenced(info)) => {
+            Tag::CodeBlock(CodeBlockKind::Fenced(owned_str(info)))
+        }
+        Tag::CodeBlock(CodeBlockKind::Indented) => Tag::CodeBlock(CodeBlockKind::Indented),
+        Tag::FootnoteDefinition(label) => Tag::FootnoteDefinition(owned_str(label)),
+        Tag::Link(kind, url, title) => Tag::Link(kind, owned_str(url), owned_str(title)),
+        Tag::Image(kind, url, title) => Tag::Image(kind, owned_str(url), owned_str(title)),
+        Tag::Paragraph => Tag::Paragraph,
+        Tag::BlockQuote => Tag::BlockQuote,
+        Tag::List(start) => Tag::List(start),
+        Tag::Item => Tag::Item,
+        Tag::Table(alignments) => Tag::Table(alignments),
+        Tag::TableHead => Tag::TableHead,
+        Tag::TableRow => Tag::TableRow,
+        Tag::TableCell => Tag::TableCell,
+        Tag::Emphasis => Tag::Emphasis,
+        Tag::Strong => Tag::Strong,
+        Tag::Strikethrough => Tag::Strikethrough,
+    }
+}
+
+fn owned_event(event: Event<'_>) -> Event<'static> {
+    match event {
+        Event::Start(tag) => Event::Start(owned_tag(tag)),
+        Event::End(tag) => Event::End(owned_tag(tag)),
+        Event::Text(text) => Event::Text(owned_str(text)),
+        Event::Code(text) => Event::Code(owned_str(text)),
+        Event::Html(text) => Event::Html(owned_str(text)),
+        Event::FootnoteReference(label) => Event::FootnoteReference(owned_str(label)),
+        Event::SoftBreak => Event::SoftBreak,
+        Event::HardBreak => Event::HardBreak,
+        Event::Rule => Event::Rule,
+        Event::TaskListMarker(checked) => Event::TaskListMarker(checked),
+    }
+}
+
+// 解析结果缓存在 egui 的 memory 中, 文本不变时不会每帧重新解析
+#[derive(Default)]
+struct MarkdownParser;
+
+impl ComputerMut<&str, Arc<Vec<Event<'static>>>> for MarkdownParser {
+    fn compute(&mut self, source: &str) -> Arc<Vec<Event<'static>>> {
+        let options = Options::ENABLE_TABLES
+            | Options::ENABLE_STRIKETHROUGH
+            | Options::ENABLE_TASKLISTS
+            | Options::ENABLE_FOOTNOTES;
+        Arc::new(Parser::new_ext(source, options).map(owned_event).collect())
+    }
+}
+
+type MarkdownCache = FrameCache<Arc<Vec<Event<'static>>>, MarkdownParser>;
+
+// 渲染 Markdown 文本, 本地链接被点击时返回链接地址
+pub fn markdown_ui(
+    ui: &mut Ui,
+    source: &str,
+    base_dir: Option<&Path>,
+    images: &mut HashMap<String, Option<RetainedImage>>,
+) -> Option<String> {
+    let mut resources = Resources {
+        base_dir,
+        images,
+        open_request: None,
+    };
+    let text_styles = &ui.style().text_styles;
+    let mut renderer = Renderer {
+        custom_headings: text_styles.contains_key(&heading2())
+            && text_styles.contains_key(&heading3()),
+        ..Default::default()
+    };
+    let events = ui
+        .ctx()
+        .memory_mut(|mem| mem.caches.cache::<MarkdownCache>().get(source));
+    for event in events.iter().cloned() {
+        renderer.event(ui, &mut resources, event);
+    }
+    renderer.flush(ui, &mut resources);
+    resources.open_request
+}
+
+// 可以从磁盘加载文档的 Markdown 查看器
+pub struct MarkdownViewer {
+    source: String,
+    path: Option<PathBuf>,
+    // 相对路径的图片和链接基于该目录
+    base_dir: Option<PathBuf>,
+    images: HashMap<String, Option<RetainedImage>>,
+}
+
+impl MarkdownViewer {
+    pub fn new(source: impl Into<String>, base_dir: Option<PathBuf>) -> Self {
+        Self {
+            source: source.into(),
+            path: None,
+            base_dir,
+            images: HashMap::new(),
+        }
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn open(&mut self, path: &Path) -> std::io::Result<()> {
+        self.source = std::fs::read_to_string(path)?;
+        self.base_dir = path.parent().map(Path::to_owned);
+        self.path = Some(path.to_owned());
+        self.images.clear();
+        Ok(())
+    }
+
+    fn open_and_report(&mut self, path: &Path, toasts: &mut Toasts) {
+        if let Err(err) = self.open(path) {
+            toasts.error(tr_args(
+                "file-open-failed",
+                &[("path", &path.display()), ("error", &err)],
+            ));
+        }
+    }
+
+    pub fn ui(&mut self, ui: &mut Ui, toasts: &mut Toasts) {
+        ui.horizontal(|ui| {
+            if ui.button(format!("📂 {}", tr("open"))).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("Markdown", &["md", "markdown"])
+                    .pick_file()
+                {
+                    self.open_and_report(&path, toasts);
+                }
+            }
+            if let Some(path) = self.path.clone() {
+                if ui.button(format!("⟲ {}", tr("markdown-reload"))).clicked() {
+                    self.open_and_report(&path, toasts);
+                }
+                ui.monospace(path.display().to_string());
+            }
+        });
+        ui.separator();
+
+        let open_request = egui::ScrollArea::vertical()
+            .auto_shrink([false; 2])
+            .show(ui, |ui| {
+                markdown_ui(ui, &self.source, self.base_dir.as_deref(), &mut self.images)
+            })
+            .inner;
+
+        // 打开相对路径的 Markdown 链接, 忽略页内锚点
+        if let Some(url) = open_request {
+            let url = url.split('#').next().unwrap_or_default();
+            if !url.is_empty() {
+                let path = match &self.base_dir {
+                    Some(dir) => dir.join(url),
+                    None => PathBuf::from(url),
+                };
+                self.open_and_report(&path, toasts);
+            }
+        }
+    }
+}
@@ -0,0 +1,402 @@
+// 桌面通知. D-Bus 后端需要 notifications feature, 只支持 Linux
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Notification {
+    pub title: String,
+    pub body: String,
+    // (key, label), 点击后通过 NotificationEvent::ActionInvoked 返回 key
+    pub actions: Vec<(String, String)>,
+    pub urgency: Urgency,
+}
+
+impl Notification {
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    // key 为 "default" 的动作在点击通知本身时触发
+    pub fn action(mut self, key: impl Into<String>, label: impl Into<String>) -> Self {
+        self.actions.push((key.into(), label.into()));
+        self
+    }
+
+    pub fn urgency(mut self, urgency: Urgency) -> Self {
+        self.urgency = urgency;
+        self
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NotificationEvent {
+    ActionInvoked { id: u32, action: String },
+    // reason: 1 超时, 2 用户关闭, 3 CloseNotification, 4 其他
+    Closed { id: u32, reason: u32 },
+}
+
+#[cfg(all(feature = "notifications", target_os = "linux"))]
+pub use dbus::DbusNotifier;
+
+#[cfg(all(feature = "notifications", target_os = "linux"))]
+mod dbus {
+    use std::collections::HashMap;
+    use std::sync::mpsc;
+
+    use zbus::blocking::{Connection, Proxy};
+    use zbus::zvariant::Value;
+
+    use super::{Notification, NotificationEvent, Urgency};
+
+    const DESTINATION: &str = "org.freedesktop.Notifications";
+    const PATH: &str = "/org/freedesktop/Notifications";
+    const INTERFACE: &str = "org.freedesktop.Notifications";
+
+    // freedesktop 通知服务的客户端. 信号由后台线程接收, 收到后唤醒界面
+    pub struct DbusNotifier {
+        proxy: Proxy<'static>,
+        app_name: String,
+        events: mpsc::Receiver<NotificationEvent>,
+    }
+
+    impl DbusNotifier {
+        pub fn session(app_name: &str, ctx: &egui::Context) -> zbus::Result<Self> {
+            Self::with_connection(&Connection::session()?, app_name, ctx)
+        }
+
+        // 测试时连接到 MockNotificationServer::pair 返回的连接
+        pub fn with_connection(
+            connection: &Connection,
+            app_name: &str,
+            ctx: &egui::Context,
+        ) -> zbus::Result<Self> {
+            let proxy = Proxy::new(connection, DESTINATION, PATH, INTERFACE)?;
+            let (tx, events) = mpsc::channel();
+            let signals = proxy.receive_all_signals()?;
+            let ctx = ctx.clone();
+            // 连接关闭或 DbusNotifier 被丢弃后的下一个信号时退出
+            std::thread::Builder::new()
+                .name("notification signals".to_owned())
+                .spawn(move || {
+                    for signal in signals {
+                        let Some(member) = signal.member() else {
+                            continue;
+                        };
+                        let event = match member.as_str() {
+                            "ActionInvoked" => {
+                                signal.body::<(u32, String)>().map(|(id, action)| {
+                                    NotificationEvent::ActionInvoked { id, action }
+                                })
+                            }
+                            "NotificationClosed" => signal
+                                .body::<(u32, u32)>()
+                                .map(|(id, reason)| NotificationEvent::Closed { id, reason }),
+                            _ => continue,
+                        };
+                        match event {
+                            Ok(event) => {
+                                if tx.send(event).is_err() {
+                                    break;
+                                }
+                                ctx.request_repaint();
+                            }
+                            Err(err) => log::warn!("Invalid notification signal: {err}"),
+                        }
+                    }
+                })
+                .map_err(zbus::Error::from)?;
+            Ok(Self {
+                proxy,
+                app_name: app_name.to_owned(),
+                events,
+            })
+        }
+
+        // replaces_id 不为 0 时替换之前的通知
+        pub fn notify(&self, notification: &Notification, replaces_id: u32) -> zbus::Result<u32> {
+            let actions: Vec<&str> = notification
+                .actions
+                .iter()
+                .flat_map(|(key, label)| [key.as_str(), label.as_str()])
+                .collect();
+            let urgency: u8 = match notification.urgency {
+                Urgency::Low => 0,
+                Urgency::Normal => 1,
+                Urgency::Critical => 2,
+            };
+            let hints = HashMap::from([("urgency", Value::U8(urgency))]);
+            self.proxy.call(
+                "Notify",
+                &(
+                    self.app_name.as_str(),
+                    replaces_id,
+                    "",
+                    notification.title.as_str(),
+                    notification.body.as_str(),
+                    actions,
+                    hints,
+                    -1i32,
+                ),
+            )
+        }
+
+        pub fn close(&self, id: u32) -> zbus::Result<()> {
+            self.proxy.call("CloseNotification", &(id,))
+        }
+
+        // 在 update 中调用, 取出所有收到的事件
+        pub fn poll_events(&self) -> Vec<NotificationEvent> {
+            self.events.try_iter().collect()
+        }
+    }
+
+    // 本地的假通知服务, 只用于测试
+    #[cfg(test)]
+    pub(super) mod mock {
+        use std::collections::HashMap;
+        use std::os::unix::net::UnixStream;
+        use std::sync::{Arc, Mutex};
+
+        use zbus::blocking::{Connection, ConnectionBuilder};
+        use zbus::zvariant::OwnedValue;
+        use zbus::{dbus_interface, SignalContext};
+
+        use super::{INTERFACE, PATH};
+        use crate::notify::{Notification, Urgency};
+
+        #[derive(Default)]
+        struct MockState {
+            next_id: u32,
+            notifications: Vec<(u32, Notification)>,
+        }
+
+        struct MockInterface {
+            state: Arc<Mutex<MockState>>,
+        }
+
+        #[dbus_interface(name = "org.freedesktop.Notifications")]
+        impl MockInterface {
+            #[allow(clippy::too_many_arguments)]
+            fn notify(
+                &self,
+                _app_name: String,
+                replaces_id: u32,
+                _app_icon: String,
+                summary: String,
+                body: String,
+                actions: Vec<String>,
+                hints: HashMap<String, OwnedValue>,
+                _expire_timeout: i32,
+            ) -> u32 {
+                let urgency = match hints
+                    .get("urgency")
+                    .map(|value| u8::try_from(value.clone()))
+                {
+                    Some(Ok(0)) => Urgency::Low,
+                    Some(Ok(2)) => Urgency::Critical,
+                    _ => Urgency::Normal,
+                };
+                let notification = Notification {
+                    title: summary,
+                    body,
+                    actions: actions
+                        .chunks_exact(2)
+                        .map(|pair| (pair[0].clone(), pair[1].clone()))
+                        .collect(),
+                    urgency,
+                };
+                let mut state = self.state.lock().expect("mock notifications poisoned");
+                let id = if replaces_id != 0 {
+                    state.notifications.retain(|(id, _)| *id != replaces_id);
+                    replaces_id
+                } else {
+                    state.next_id += 1;
+                    state.next_id
+                };
+                state.notifications.push((id, notification));
+                id
+            }
+
+            async fn close_notification(
+                &self,
+                id: u32,
+                #[zbus(signal_context)] ctxt: SignalContext<'_>,
+            ) -> zbus::fdo::Result<()> {
+                self.state
+                    .lock()
+                    .expect("mock notifications poisoned")
+                    .notifications
+                    .retain(|(other, _)| *other != id);
+                Self::notification_closed(&ctxt, id, 3).await?;
+                Ok(())
+            }
+
+            #[dbus_interface(signal)]
+            async fn notification_closed(
+                ctxt: &SignalContext<'_>,
+                id: u32,
+                reason: u32,
+            ) -> zbus::Result<()>;
+
+            fn get_capabilities(&self) -> Vec<String> {
+                vec!["actions".to_owned(), "body".to_owned()]
+            }
+
+            fn get_server_information(&self) -> (String, String, String, String) {
+                (
+                    "mock".to_owned(),
+                    "egui-demo".to_owned(),
+                    env!("CARGO_PKG_VERSION").to_owned(),
+                    "1.2".to_owned(),
+                )
+            }
+        }
+
+        // 本地的假通知服务, 通过点对点连接提供 org.freedesktop.Notifications,
+        // 不需要会话总线. 可以模拟用户点击和关闭通知
+        pub struct MockNotificationServer {
+            connection: Connection,
+            state: Arc<Mutex<MockState>>,
+        }
+
+        impl MockNotificationServer {
+            // 返回服务端和连接到它的客户端连接
+            pub fn pair() -> zbus::Result<(Self, Connection)> {
+                let (server_stream, client_stream) =
+                    UnixStream::pair().map_err(zbus::Error::from)?;
+                let state = Arc::new(Mutex::new(MockState::default()));
+                let interface = MockInterface {
+                    state: state.clone(),
+                };
+                // 两端的握手需要同时进行
+                let server = std::thread::spawn(move || {
+                    let guid = zbus::Guid::generate();
+                    ConnectionBuilder::unix_stream(server_stream)
+                        .server(&guid)
+                        .p2p()
+                        .serve_at(PATH, interface)?
+                        .build()
+                });
+                let client = ConnectionBuilder::unix_stream(client_stream)
+                    .p2p()
+                    .build()?;
+                let connection = server
+                    .join()
+                    .map_err(|_| zbus::Error::Failure("mock server panicked".to_owned()))??;
+                Ok((Self { connection, state }, client))
+            }
+
+            // 当前显示的通知
+            pub fn notifications(&self) -> Vec<(u32, Notification)> {
+                self.state
+                    .lock()
+                    .expect("mock notifications poisoned")
+                    .notifications
+                    .clone()
+            }
+
+            // 模拟用户点击通知上的动作, 通知随后关闭
+            pub fn invoke_action(&self, id: u32, action: &str) -> zbus::Result<()> {
+                self.connection.emit_signal(
+                    None::<()>,
+                    PATH,
+                    INTERFACE,
+                    "ActionInvoked",
+                    &(id, action),
+                )?;
+                self.dismiss(id, 2)
+            }
+
+            pub fn dismiss(&self, id: u32, reason: u32) -> zbus::Result<()> {
+                self.state
+                    .lock()
+                    .expect("mock notifications poisoned")
+                    .notifications
+                    .retain(|(other, _)| *other != id);
+                self.connection.emit_signal(
+                    None::<()>,
+                    PATH,
+                    INTERFACE,
+                    "NotificationClosed",
+                    &(id, reason),
+                )
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "notifications", target_os = "linux"))]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::dbus::mock::MockNotificationServer;
+    use super::*;
+
+    // 信号由后台线程转发, 最多等待 5 秒
+    fn wait_events(notifier: &DbusNotifier, count: usize) -> Vec<NotificationEvent> {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut events = Vec::new();
+        while events.len() < count && Instant::now() < deadline {
+            events.extend(notifier.poll_events());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        events
+    }
+
+    #[test]
+    fn notify_replace_close_and_actions() {
+        let ctx = egui::Context::default();
+        let (server, client) = MockNotificationServer::pair().unwrap();
+        let notifier = DbusNotifier::with_connection(&client, "test", &ctx).unwrap();
+        let notification = Notification::new("Title")
+            .body("Body")
+            .action("default", "Show")
+            .action("stop", "Stop")
+            .urgency(Urgency::Critical);
+
+        let id = notifier.notify(&notification, 0).unwrap();
+        assert_eq!(server.notifications(), vec![(id, notification.clone())]);
+        let replaced = notification.clone().body("Again");
+        assert_eq!(notifier.notify(&replaced, id).unwrap(), id);
+        assert_eq!(server.notifications(), vec![(id, replaced)]);
+
+        server.invoke_action(id, "stop").unwrap();
+        assert_eq!(
+            wait_events(&notifier, 2),
+            vec![
+                NotificationEvent::ActionInvoked {
+                    id,
+                    action: "stop".to_owned()
+                },
+                NotificationEvent::Closed { id, reason: 2 },
+            ]
+        );
+        assert!(server.notifications().is_empty());
+
+        let other = notifier.notify(&notification, 0).unwrap();
+        assert_ne!(other, id);
+        notifier.close(other).unwrap();
+        assert!(server.notifications().is_empty());
+        assert_eq!(
+            wait_events(&notifier, 1),
+            vec![NotificationEvent::Closed {
+                id: other,
+                reason: 3
+            }]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::io;
+use std::path::Path;
+
+use egui::{RawInput, Rect};
+
+// 文件格式版本, 不兼容的修改需要递增
+pub const SESSION_VERSION: u32 = 1;
+
+// 录制的交互会话, 每一项是一帧的原始输入.
+// RawInput::time 是相对录制开始的秒数
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct Session {
+    pub version: u32,
+    // 录制开始时的窗口大小, 无界面回放时使用
+    pub screen_rect: Option<Rect>,
+    pub pixels_per_point: Option<f32>,
+    pub frames: Vec<RawInput>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            version: SESSION_VERSION,
+            screen_rect: None,
+            pixels_per_point: None,
+            frames: Vec::new(),
+        }
+    }
+}
+
+impl Session {
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        let session: Session = serde_json::from_str(&json).map_err(io::Error::from)?;
+        if session.version != SESSION_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported session version {}", session.version),
+            ));
+        }
+        Ok(session)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string(self).map_err(io::Error::from)?;
+        std::fs::write(path, json)
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // 录制时长 (秒)
+    pub fn duration(&self) -> f64 {
+        self.frames.last().and_then(|raw| raw.time).unwrap_or(0.0)
+    }
+
+    // 按 speed 缩放时间后的输入, 用于无界面回放.
+    // 第一帧带上录制时的窗口大小, 保证布局一致
+    pub fn inputs(&self, speed: f64) -> impl Iterator<Item = RawInput> + '_ {
+        let speed = speed.max(f64::EPSILON);
+        self.frames.iter().enumerate().map(move |(index, raw)| {
+            let mut raw = raw.clone();
+            raw.time = raw.time.map(|time| time / speed);
+            if index == 0 {
+                raw.screen_rect = raw.screen_rect.or(self.screen_rect);
+                raw.pixels_per_point = raw.pixels_per_point.or(self.pixels_per_point);
+            }
+            raw
+        })
+    }
+}
+
+// 逐帧录制输入, 没有事件的帧会被跳过
+#[derive(Default)]
+pub struct Recorder {
+    session: Session,
+    start: Option<f64>,
+    hovered_files: usize,
+    screen_rect: Option<Rect>,
+}
+
+impl Recorder {
+    pub fn len(&self) -> usize {
+        self.session.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.session.is_empty()
+    }
+
+    // 每帧调用一次, now 是 egui 的帧时间
+    pub fn record(&mut self, raw: &RawInput, now: f64) {
+        let time = raw.time.unwrap_or(now);
+        let start = *self.start.get_or_insert_with(|| {
+            self.session.screen_rect = raw.screen_rect;
+            self.session.pixels_per_point = raw.pixels_per_point;
+            time
+        });
+        let hovered_changed = raw.hovered_files.len() != self.hovered_files;
+        self.hovered_files = raw.hovered_files.len();
+        // 窗口大小变化也需要记录, 否则回放时指针位置对不上
+        let resized = raw.screen_rect.is_some() && raw.screen_rect != self.screen_rect;
+        if resized {
+            self.screen_rect = raw.screen_rect;
+        }
+        if raw.events.is_empty() && raw.dropped_files.is_empty() && !hovered_changed && !resized {
+            return;
+        }
+        let mut raw = raw.clone();
+        raw.time = Some(time - start);
+        self.session.frames.push(raw);
+    }
+
+    pub fn finish(self) -> Session {
+        self.session
+    }
+}
+
+// 在真实窗口中回放: 到期的录制帧替换本帧的输入
+pub struct Player {
+    session: Session,
+    next: usize,
+    speed: f64,
+    start: Option<f64>,
+}
+
+impl Player {
+    pub fn new(session: Session, speed: f64) -> Self {
+        Self {
+            session,
+            next: 0,
+            speed: speed.max(f64::EPSILON),
+            start: None,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.session.len()
+    }
+
+    // 已回放的帧数和总帧数
+    pub fn progress(&self) -> (usize, usize) {
+        (self.next, self.session.len())
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.speed
+    }
+
+    // 在 update 中任何控件读取输入之前调用, 返回本帧是否使用了录制的输入.
+    // 每帧最多回放一帧, 以保留按下和抬起的顺序. 回放期间实时输入全部丢弃,
+    // 没有到期的帧时使用空输入, 保证回放结果确定.
+    // egui 在 update 之前就根据实时输入处理了 Tab 切换焦点, 这里锁定当前焦点让它失效;
+    // 同样的原因, 录制的 Tab 在真实窗口中不会切换焦点
+    pub fn apply(&mut self, ctx: &egui::Context) -> bool {
+        let Some(frame) = self.session.frames.get(self.next) else {
+            return false;
+        };
+        lock_focus(ctx, true);
+        let now = ctx.input(|i| i.time);
+        let start = *self.start.get_or_insert(now);
+        let due = start + frame.time.unwrap_or(0.0) / self.speed;
+        let recorded = now >= due;
+        let mut raw = if recorded {
+            frame.clone()
+        } else {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(due - now));
+            RawInput::default()
+        };
+        // 保持 egui 的时间单调递增, 窗口大小使用当前窗口的
+        raw.time = Some(now);
+        raw.screen_rect = None;
+        raw.pixels_per_point = None;
+        ctx.input_mut(|i| *i = std::mem::take(i).begin_frame(raw, false));
+        if recorded {
+            self.next += 1;
+            ctx.request_repaint();
+        }
+        recorded
+    }
+}
+
+// 锁定或解锁当前有焦点的控件, 锁定时 Tab 不会移动焦点
+pub(crate) fn lock_focus(ctx: &egui::Context, lock: bool) {
+    ctx.memory_mut(|mem| {
+        if let Some(id) = mem.focus() {
+            mem.lock_focus(id, lock);
+        }
+    });
+}
+
+// 无界面回放, 用于回归测试: 每一帧输入都调用一次 run_ui,
+// 最后再运行一帧空输入, 让回放的最后一个事件生效
+pub fn replay_headless(
+    ctx: &egui::Context,
+    session: &Session,
+    speed: f64,
+    mut run_ui: impl FnMut(&egui::Context),
+) {
+    let mut last_time = 0.0;
+    for raw in session.inputs(speed) {
+        last_time = raw.time.unwrap_or(last_time);
+        let _ = ctx.run(raw, &mut run_ui);
+    }
+    let idle = RawInput {
+        time: Some(last_time + 1.0 / 60.0),
+        ..Default::default()
+    };
+    let _ = ctx.run(idle, &mut run_ui);
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{pos2, vec2, Event, Key, Modifiers};
+
+    use super::*;
+    use crate::app::MyApp;
+
+    fn press(key: Key) -> [Event; 2] {
+        [true, false].map(|pressed| Event::Key {
+            key,
+            pressed,
+            repeat: false,
+            modifiers: Modifiers::NONE,
+        })
+    }
+
+    fn record(events: Vec<Event>) -> Session {
+        let mut recorder = Recorder::default();
+        let mut time = 0.0;
+        // 每个事件之后插入一个空帧, 和真实录制一样
+        for events in events.into_iter().flat_map(|event| [vec![event], vec![]]) {
+            time += 0.1;
+            let raw = RawInput {
+                time: Some(time),
+                screen_rect: Some(Rect::from_min_size(pos2(0.0, 0.0), vec2(400.0, 1000.0))),
+                events,
+                ..Default::default()
+            };
+            recorder.record(&raw, time);
+        }
+        recorder.finish()
+    }
+
+    #[test]
+    fn live_input_is_ignored_during_replay() {
+        let ctx = egui::Context::default();
+        let mut app = MyApp::with_context(&ctx);
+        // 先把焦点移到姓名输入框
+        let mut time = 0.0;
+        for _ in 0..7 {
+            time += 1.0 / 60.0;
+            let raw = RawInput {
+                time: Some(time),
+                events: press(Key::Tab).into(),
+                ..Default::default()
+            };
+            let _ = ctx.run(raw, |ctx| app.ui(ctx));
+        }
+        app.replay(record(vec![Event::Text(" Li".to_owned())]), 1.0);
+        let start = time;
+        while app.is_replaying() {
+            assert!(time - start < 10.0, "replay did not finish");
+            time += 1.0 / 60.0;
+            // 回放期间用户继续输入, 点击和按 Tab
+            let mut events = vec![
+                Event::Text("x".to_owned()),
+                Event::PointerButton {
+                    pos: pos2(10.0, 10.0),
+                    button: egui::PointerButton::Primary,
+                    pressed: true,
+                    modifiers: Modifiers::NONE,
+                },
+            ];
+            events.extend(press(Key::Tab));
+            let raw = RawInput {
+                time: Some(time),
+                events,
+                ..Default::default()
+            };
+            let _ = ctx.run(raw, |ctx| app.ui(ctx));
+        }
+        assert_eq!(app.name(), "Zzrk Li");
+    }
+
+    #[test]
+    fn replay_round_trip() {
+        let tab = press(Key::Tab);
+        let mut events = Vec::new();
+        // 姓名输入框
+        events.extend(std::iter::repeat_n(tab.clone(), 7).flatten());
+        events.push(Event::Text(" Li".to_owned()));
+        // 年龄滑块
+        events.extend(tab.clone());
+        events.extend(std::iter::repeat_n(press(Key::ArrowRight), 3).flatten());
+        // 文本编辑器
+        events.extend(std::iter::repeat_n(tab, 10).flatten());
+        events.push(Event::Text("你好".to_owned()));
+        let session = record(events);
+
+        let json = serde_json::to_string(&session).unwrap();
+        let session: Session = serde_json::from_str(&json).unwrap();
+        assert_eq!(session.version, SESSION_VERSION);
+
+        let ctx = egui::Context::default();
+        let mut app = MyApp::with_context(&ctx);
+        let text = app.text().to_owned();
+        replay_headless(&ctx, &session, 4.0, |ctx| app.ui(ctx));
+        assert_eq!(app.name(), "Zzrk Li");
+        assert_eq!(app.age(), 21);
+        assert_ne!(app.text(), text);
+        assert!(app.text().contains("你好"));
+    }
+}
@@ -0,0 +1,206 @@
+use egui::style::{Selection, WidgetVisuals};
+use egui::{Color32, Stroke, Style, Ui, Visuals};
+
+use crate::i18n::tr;
+
+const MIN_UI_SCALE: f32 = 0.5;
+const MAX_UI_SCALE: f32 = 3.0;
+const ZOOM_STEP: f32 = 0.1;
+
+// 显示相关的无障碍设置, 按用户持久化
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    // 相对系统缩放的界面缩放比例
+    pub ui_scale: f32,
+    pub high_contrast: bool,
+    // 关闭展开、折叠等动画
+    pub reduced_motion: bool,
+    // 所有文字样式都不小于该字号 (pt), 0 表示不限制
+    pub min_font_size: f32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            high_contrast: false,
+            reduced_motion: false,
+            min_font_size: 0.0,
+        }
+    }
+}
+
+impl DisplaySettings {
+    fn set_ui_scale(&mut self, ui_scale: f32) {
+        // 保留一位小数, 多次缩放后不会出现 1.0000001
+        self.ui_scale = ((ui_scale * 10.0).round() / 10.0).clamp(MIN_UI_SCALE, MAX_UI_SCALE);
+    }
+
+    pub fn zoom_in(&mut self) {
+        self.set_ui_scale(self.ui_scale + ZOOM_STEP);
+    }
+
+    pub fn zoom_out(&mut self) {
+        self.set_ui_scale(self.ui_scale - ZOOM_STEP);
+    }
+
+    pub fn reset_zoom(&mut self) {
+        self.ui_scale = 1.0;
+    }
+
+    // native 为系统的缩放, 无界面运行时没有
+    pub fn pixels_per_point(&self, native: Option<f32>) -> f32 {
+        native.unwrap_or(1.0) * self.ui_scale
+    }
+
+    // 在 base 的基础上应用设置, base.visuals.dark_mode 决定高对比度使用深色还是浅色
+    pub fn style(&self, base: &Style) -> Style {
+        let mut style = base.clone();
+        for font_id in style.text_styles.values_mut() {
+            font_id.size = font_id.size.max(self.min_font_size);
+        }
+        if self.high_contrast {
+            style.visuals = high_contrast_visuals(base.visuals.dark_mode);
+        }
+        if self.reduced_motion {
+            style.animation_time = 0.0;
+        }
+        style
+    }
+
+    pub fn apply(&self, ctx: &egui::Context, base: &Style, native_pixels_per_point: Option<f32>) {
+        ctx.set_style(self.style(base));
+        ctx.set_pixels_per_point(self.pixels_per_point(native_pixels_per_point));
+    }
+
+    // 设置面板, 返回是否有修改
+    pub fn ui(&mut self, ui: &mut Ui) -> bool {
+        let before = self.clone();
+        egui::Grid::new("display_settings")
+            .num_columns(2)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                let label = ui.label(tr("settings-ui-scale"));
+                ui.horizontal(|ui| {
+                    let mut percent = self.ui_scale * 100.0;
+                    let slider = egui::Slider::new(
+                        &mut percent,
+                        MIN_UI_SCALE * 100.0..=MAX_UI_SCALE * 100.0,
+                    )
+                    .step_by(10.0)
+                    .suffix("%");
+                    if ui.add(slider).labelled_by(label.id).changed() {
+                        self.set_ui_scale(percent / 100.0);
+                    }
+                    if ui.button(tr("settings-zoom-reset")).clicked() {
+                        self.reset_zoom();
+                    }
+                });
+                ui.end_row();
+
+                let label = ui.label(tr("settings-min-font-size"));
+                let slider = egui::Slider::new(&mut self.min_font_size, 0.0..=24.0)
+                    .custom_formatter(|size, decimals| {
+                        if size == 0.0 {
+                            tr("settings-min-font-size-off").to_owned()
+                        } else {
+                            let size = egui::emath::format_with_decimals_in_range(size, decimals);
+                            format!("{size} pt")
+                        }
+                    });
+                ui.add(slider).labelled_by(label.id);
+                ui.end_row();
+
+                ui.label("");
+                ui.checkbox(&mut self.high_contrast, tr("settings-high-contrast"));
+                ui.end_row();
+
+                ui.label("");
+                ui.checkbox(&mut self.reduced_motion, tr("settings-reduced-motion"));
+                ui.end_row();
+            });
+        ui.add_space(8.0);
+        if ui.button(tr("settings-reset")).clicked() {
+            *self = Self::default();
+        }
+        *self != before
+    }
+}
+
+// 黑底白字 (或白底黑字), 控件都有清晰的边框, 强调色对比度足够
+pub fn high_contrast_visuals(dark_mode: bool) -> Visuals {
+    let (background, foreground, accent) = if dark_mode {
+        (
+            Color32::BLACK,
+            Color32::WHITE,
+            Color32::from_rgb(255, 230, 0),
+        )
+    } else {
+        (Color32::WHITE, Color32::BLACK, Color32::from_rgb(0, 0, 200))
+    };
+    // 选中的文字颜色不变, 选区背景要与文字有足够的对比度
+    let selection = if dark_mode {
+        Color32::from_rgb(0, 80, 200)
+    } else {
+        Color32::from_rgb(170, 210, 255)
+    };
+    let mut visuals = if dark_mode {
+        Visuals::dark()
+    } else {
+        Visuals::light()
+    };
+    visuals.override_text_color = Some(foreground);
+    visuals.hyperlink_color = accent;
+    visuals.panel_fill = background;
+    visuals.window_fill = background;
+    visuals.extreme_bg_color = background;
+    visuals.faint_bg_color = background;
+    visuals.code_bg_color = background;
+    visuals.window_stroke = Stroke::new(2.0, foreground);
+    visuals.selection = Selection {
+        bg_fill: selection,
+        stroke: Stroke::new(2.0, foreground),
+    };
+
+    let widget = |base: WidgetVisuals, stroke: Stroke| WidgetVisuals {
+        bg_fill: background,
+        weak_bg_fill: background,
+        bg_stroke: stroke,
+        fg_stroke: Stroke::new(1.5, foreground),
+        ..base
+    };
+    let widgets = &mut visuals.widgets;
+    widgets.noninteractive = widget(widgets.noninteractive, Stroke::new(1.0, foreground));
+    widgets.inactive = widget(widgets.inactive, Stroke::new(1.0, foreground));
+    widgets.hovered = widget(widgets.hovered, Stroke::new(2.0, accent));
+    widgets.active = widget(widgets.active, Stroke::new(3.0, accent));
+    widgets.open = widget(widgets.open, Stroke::new(2.0, accent));
+    visuals
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::TextStyle;
+
+    use super::*;
+
+    #[test]
+    fn default_keeps_font_sizes() {
+        let mut base = Style::default();
+        base.text_styles
+            .get_mut(&TextStyle::Small)
+            .expect("small text style")
+            .size = 8.0;
+        let style = DisplaySettings::default().style(&base);
+        assert_eq!(style.text_styles, base.text_styles);
+
+        let settings = DisplaySettings {
+            min_font_size: 12.0,
+            ..Default::default()
+        };
+        let style = settings.style(&base);
+        assert_eq!(style.text_styles[&TextStyle::Small].size, 12.0);
+        assert!(style.text_styles.values().all(|font| font.size >= 12.0));
+    }
+}
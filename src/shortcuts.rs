@@ -0,0 +1,680 @@
+use std::collections::BTreeMap;
+
+use egui::{Align2, Event, Id, Key, KeyboardShortcut, ModifierNames, Modifiers, RichText, Vec2};
+
+use crate::a11y::Accessible;
+use crate::dialog::modal_backdrop;
+use crate::i18n::{tr, tr_args};
+use crate::ime;
+
+// egui 0.22 没有提供按名称查找按键的方法
+const KEYS: &[Key] = &[
+    Key::ArrowDown,
+    Key::ArrowLeft,
+    Key::ArrowRight,
+    Key::ArrowUp,
+    Key::Escape,
+    Key::Tab,
+    Key::Backspace,
+    Key::Enter,
+    Key::Space,
+    Key::Insert,
+    Key::Delete,
+    Key::Home,
+    Key::End,
+    Key::PageUp,
+    Key::PageDown,
+    Key::Minus,
+    Key::PlusEquals,
+    Key::Num0,
+    Key::Num1,
+    Key::Num2,
+    Key::Num3,
+    Key::Num4,
+    Key::Num5,
+    Key::Num6,
+    Key::Num7,
+    Key::Num8,
+    Key::Num9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::F16,
+    Key::F17,
+    Key::F18,
+    Key::F19,
+    Key::F20,
+];
+
+// 配置文件中的格式, 例如 "Ctrl+Shift+P"
+pub fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    shortcut.format(&ModifierNames::NAMES, false)
+}
+
+pub fn parse_shortcut(text: &str) -> Result<KeyboardShortcut, String> {
+    let mut modifiers = Modifiers::NONE;
+    let mut key = None;
+    for part in text.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => modifiers = modifiers | Modifiers::COMMAND,
+            "shift" => modifiers = modifiers | Modifiers::SHIFT,
+            "alt" | "option" => modifiers = modifiers | Modifiers::ALT,
+            "" => return Err(format!("Invalid shortcut '{text}'")),
+            name => {
+                if key.is_some() {
+                    return Err(format!("'{text}' has more than one key"));
+                }
+                key = Some(
+                    KEYS.iter()
+                        .copied()
+                        .find(|k| k.name().eq_ignore_ascii_case(name))
+                        .ok_or_else(|| format!("Unknown key '{part}'"))?,
+                );
+            }
+        }
+    }
+    let key = key.ok_or_else(|| format!("'{text}' has no key"))?;
+    Ok(KeyboardShortcut::new(modifiers, key))
+}
+
+struct Command<C> {
+    command: C,
+    // 配置中使用的标识
+    id: &'static str,
+    // 翻译 key
+    name: &'static str,
+    default: Option<KeyboardShortcut>,
+    shortcut: Option<KeyboardShortcut>,
+}
+
+// 命令注册表: 命令和快捷键的对应关系
+pub struct Shortcuts<C> {
+    commands: Vec<Command<C>>,
+    // 正在录制新快捷键的命令
+    recording: Option<usize>,
+}
+
+impl<C: Copy + PartialEq> Default for Shortcuts<C> {
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            recording: None,
+        }
+    }
+}
+
+impl<C: Copy + PartialEq> Shortcuts<C> {
+    pub fn register(
+        mut self,
+        command: C,
+        id: &'static str,
+        name: &'static str,
+        default: Option<KeyboardShortcut>,
+    ) -> Self {
+        self.commands.push(Command {
+            command,
+            id,
+            name,
+            default,
+            shortcut: default,
+        });
+        self
+    }
+
+    fn get(&self, command: C) -> Option<&Command<C>> {
+        self.commands.iter().find(|c| c.command == command)
+    }
+
+    pub fn name(&self, command: C) -> &'static str {
+        self.get(command).map_or("", |c| tr(c.name))
+    }
+
+    pub fn shortcut(&self, command: C) -> Option<KeyboardShortcut> {
+        self.get(command)?.shortcut
+    }
+
+    pub fn bind(&mut self, command: C, shortcut: Option<KeyboardShortcut>) {
+        if let Some(c) = self.commands.iter_mut().find(|c| c.command == command) {
+            c.shortcut = shortcut;
+        }
+    }
+
+    pub fn commands(&self) -> impl Iterator<Item = (C, &'static str)> + '_ {
+        self.commands.iter().map(|c| (c.command, tr(c.name)))
+    }
+
+    // 绑定到同一个快捷键的命令
+    pub fn conflicts(&self) -> Vec<(KeyboardShortcut, Vec<&'static str>)> {
+        let mut conflicts: Vec<(KeyboardShortcut, Vec<&'static str>)> = Vec::new();
+        for command in &self.commands {
+            let Some(shortcut) = command.shortcut else {
+                continue;
+            };
+            match conflicts.iter_mut().find(|(s, _)| *s == shortcut) {
+                Some((_, names)) => names.push(tr(command.name)),
+                None => conflicts.push((shortcut, vec![tr(command.name)])),
+            }
+        }
+        conflicts.retain(|(_, names)| names.len() > 1);
+        conflicts
+    }
+
+    fn has_conflict(&self, shortcut: KeyboardShortcut) -> bool {
+        self.commands
+            .iter()
+            .filter(|c| c.shortcut == Some(shortcut))
+            .count()
+            > 1
+    }
+
+    // 只保存与默认值不同的绑定, 空字符串表示取消绑定
+    pub fn to_config(&self) -> BTreeMap<String, String> {
+        self.commands
+            .iter()
+            .filter(|c| c.shortcut != c.default)
+            .map(|c| {
+                let shortcut = c.shortcut.as_ref().map(format_shortcut);
+                (c.id.to_owned(), shortcut.unwrap_or_default())
+            })
+            .collect()
+    }
+
+    // 返回无法应用的配置项, 其余的照常生效
+    pub fn apply_config(&mut self, config: &BTreeMap<String, String>) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (id, text) in config {
+            let Some(command) = self.commands.iter_mut().find(|c| c.id == id) else {
+                errors.push(format!("Unknown command '{id}'"));
+                continue;
+            };
+            if text.trim().is_empty() {
+                command.shortcut = None;
+                continue;
+            }
+            match parse_shortcut(text) {
+                Ok(shortcut) => command.shortcut = Some(shortcut),
+                Err(err) => errors.push(format!("{id}: {err}")),
+            }
+        }
+        errors
+    }
+
+    // 设置窗口关闭时调用, 否则录制状态会一直屏蔽所有快捷键
+    pub fn cancel_recording(&mut self) {
+        self.recording = None;
+    }
+
+    // 消耗本帧按下的快捷键, 返回触发的命令.
+    // enabled 返回 false 的命令不处理, 按键留给其他控件
+    pub fn poll(&self, ctx: &egui::Context, enabled: impl Fn(C) -> bool) -> Vec<C> {
+        if self.recording.is_some() {
+            return Vec::new();
+        }
+        ctx.input_mut(|i| {
+            self.commands
+                .iter()
+                .filter(|c| enabled(c.command))
+                .filter(|c| c.shortcut.is_some_and(|s| i.consume_shortcut(&s)))
+                .map(|c| c.command)
+                .collect()
+        })
+    }
+
+    // 快捷键设置界面: 点击按钮后按下新的组合键
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        if let Some(index) = self.recording {
+            let pressed = ui.input_mut(|i| {
+                let pressed = i.events.iter().find_map(|event| match event {
+                    Event::Key {
+                        key,
+                        pressed: true,
+                        modifiers,
+                        ..
+                    } => Some((*key, *modifiers)),
+                    _ => None,
+                });
+                i.events
+                    .retain(|e| !matches!(e, Event::Key { .. } | Event::Text(_)));
+                pressed
+            });
+            match pressed {
+                Some((Key::Escape, Modifiers::NONE)) => self.recording = None,
+                Some((key, modifiers)) => {
+                    // 只保留配置格式能表示的修饰键
+                    let modifiers = Modifiers {
+                        command: modifiers.command || modifiers.ctrl,
+                        ctrl: false,
+                        mac_cmd: false,
+                        ..modifiers
+                    };
+                    self.commands[index].shortcut = Some(KeyboardShortcut::new(modifiers, key));
+                    self.recording = None;
+                }
+                None => {}
+            }
+        }
+
+        egui::Grid::new("shortcuts_grid")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for index in 0..self.commands.len() {
+                    let command = &self.commands[index];
+                    ui.label(tr(command.name));
+                    let text = if self.recording == Some(index) {
+                        RichText::new(tr("shortcuts-press-keys")).italics()
+                    } else {
+                        match command.shortcut {
+                            Some(shortcut) => {
+                                let text = RichText::new(ui.ctx().format_shortcut(&shortcut));
+                                if self.has_conflict(shortcut) {
+                                    text.color(ui.visuals().error_fg_color)
+                                } else {
+                                    text
+                                }
+                            }
+                            None => RichText::new("—").weak(),
+                        }
+                    };
+                    let name = format!("{}: {}", tr(command.name), text.text());
+                    if ui
+                        .button(text)
+                        .on_hover_text(tr("shortcuts-rebind"))
+                        .accessible_name(name)
+                        .clicked()
+                    {
+                        self.recording = Some(index);
+                    }
+                    ui.horizontal(|ui| {
+                        let command = &mut self.commands[index];
+                        if ui
+                            .add_enabled(command.shortcut.is_some(), egui::Button::new("✖"))
+                            .on_hover_text(tr("shortcuts-unbind"))
+                            .accessible_name(tr("shortcuts-unbind"))
+                            .clicked()
+                        {
+                            command.shortcut = None;
+                        }
+                        if ui
+                            .add_enabled(
+                                command.shortcut != command.default,
+                                egui::Button::new("⟲"),
+                            )
+                            .on_hover_text(tr("shortcuts-reset"))
+                            .accessible_name(tr("shortcuts-reset"))
+                            .clicked()
+                        {
+                            command.shortcut = command.default;
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        for (shortcut, names) in self.conflicts() {
+            ui.label(
+                RichText::new(format!(
+                    "⚠ {}",
+                    tr_args(
+                        "shortcuts-conflict",
+                        &[
+                            ("shortcut", &ui.ctx().format_shortcut(&shortcut)),
+                            ("commands", &names.join(", ")),
+                        ],
+                    )
+                ))
+                .color(ui.visuals().error_fg_color),
+            );
+        }
+    }
+}
+
+// 模糊匹配: query 的字符按顺序出现在 text 中即可, 连续匹配和单词开头得分更高
+pub fn fuzzy_match(query: &str, text: &str) -> Option<i32> {
+    let mut score = 0;
+    let mut chars = text.char_indices().peekable();
+    let mut previous_match: Option<usize> = None;
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let q = q.to_lowercase().next().unwrap_or(q);
+        loop {
+            let (index, c) = chars.next()?;
+            if c.to_lowercase().next() != Some(q) {
+                continue;
+            }
+            score += 1;
+            if previous_match.is_some_and(|p| p + 1 == index) {
+                score += 5;
+            }
+            let word_start = index == 0
+                || text[..index]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|p| !p.is_alphanumeric());
+            if word_start {
+                score += 3;
+            }
+            previous_match = Some(index + c.len_utf8() - 1);
+            break;
+        }
+    }
+    // 文本越短越接近
+    Some(score * 100 - text.len() as i32)
+}
+
+// Ctrl+Shift+P 打开的命令面板
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    selected: usize,
+    // 打开后第一帧需要聚焦输入框
+    focus: bool,
+}
+
+impl CommandPalette {
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.selected = 0;
+        self.focus = true;
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    // 返回选中的命令
+    pub fn show<C: Copy + PartialEq>(
+        &mut self,
+        ctx: &egui::Context,
+        shortcuts: &Shortcuts<C>,
+    ) -> Option<C> {
+        if !self.open {
+            return None;
+        }
+        modal_backdrop(ctx);
+
+        let mut matches: Vec<(i32, C, &'static str)> = shortcuts
+            .commands()
+            .filter_map(|(command, name)| {
+                fuzzy_match(&self.query, name).map(|score| (score, command, name))
+            })
+            .collect();
+        matches.sort_by_key(|m| std::cmp::Reverse(m.0));
+
+        let (escape, enter, up, down) = ctx.input_mut(|i| {
+            (
+                i.consume_key(Modifiers::NONE, Key::Escape),
+                i.consume_key(Modifiers::NONE, Key::Enter),
+                i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                i.consume_key(Modifiers::NONE, Key::ArrowDown),
+            )
+        });
+        if escape {
+            self.open = false;
+            return None;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down {
+            self.selected += 1;
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = enter
+            .then(|| matches.get(self.selected).map(|m| m.1))
+            .flatten();
+        egui::Window::new("Command palette")
+            .id(Id::new("command_palette"))
+            .title_bar(false)
+            .resizable(false)
+            .fixed_size([360.0, 0.0])
+            .anchor(Align2::CENTER_TOP, Vec2::new(0.0, 40.0))
+            .show(ctx, |ui| {
+                let edit = ime::show(
+                    ui,
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr("palette-hint"))
+                        .desired_width(f32::INFINITY),
+                )
+                .response;
+                if self.focus {
+                    edit.request_focus();
+                    self.focus = false;
+                }
+                if edit.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                if matches.is_empty() {
+                    ui.weak(tr("palette-no-matches"));
+                }
+                for (index, (_, command, name)) in matches.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let label = ui.selectable_label(index == self.selected, *name);
+                        if label.clicked() {
+                            chosen = Some(*command);
+                        }
+                        if let Some(shortcut) = shortcuts.shortcut(*command) {
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    ui.weak(ctx.format_shortcut(&shortcut));
+                                },
+                            );
+                        }
+                    });
+                }
+            });
+
+        if chosen.is_some() {
+            self.open = false;
+        }
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::RawInput;
+
+    use super::*;
+
+    fn shortcut(modifiers: Modifiers, key: Key) -> KeyboardShortcut {
+        KeyboardShortcut::new(modifiers, key)
+    }
+
+    fn press(
+        ctx: &egui::Context,
+        shortcuts: &Shortcuts<u32>,
+        modifiers: Modifiers,
+        key: Key,
+    ) -> Vec<u32> {
+        let raw = RawInput {
+            events: vec![Event::Key {
+                key,
+                pressed: true,
+                repeat: false,
+                modifiers,
+            }],
+            ..Default::default()
+        };
+        let mut commands = Vec::new();
+        let _ = ctx.run(raw, |ctx| commands = shortcuts.poll(ctx, |_| true));
+        commands
+    }
+
+    #[test]
+    fn parse_and_format_round_trip() {
+        for shortcut in [
+            shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::P),
+            shortcut(Modifiers::ALT, Key::F4),
+            shortcut(Modifiers::COMMAND, Key::PlusEquals),
+            shortcut(Modifiers::NONE, Key::Escape),
+        ] {
+            let text = format_shortcut(&shortcut);
+            assert_eq!(parse_shortcut(&text), Ok(shortcut), "{text}");
+        }
+        assert_eq!(
+            parse_shortcut(" cmd + Shift + p "),
+            Ok(shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::P))
+        );
+        assert_eq!(
+            parse_shortcut("option+f4"),
+            Ok(shortcut(Modifiers::ALT, Key::F4))
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(parse_shortcut("Ctrl+").is_err());
+        assert!(parse_shortcut("Ctrl").is_err());
+        assert!(parse_shortcut("Ctrl+Foo").is_err());
+        assert!(parse_shortcut("A+B").is_err());
+    }
+
+    #[test]
+    fn conflicts() {
+        let save = shortcut(Modifiers::COMMAND, Key::S);
+        let mut shortcuts = Shortcuts::default()
+            .register(1, "save", "test-save", Some(save))
+            .register(2, "share", "test-share", None)
+            .register(
+                3,
+                "quit",
+                "quit",
+                Some(shortcut(Modifiers::COMMAND, Key::Q)),
+            );
+        assert!(shortcuts.conflicts().is_empty());
+        shortcuts.bind(2, Some(save));
+        assert_eq!(
+            shortcuts.conflicts(),
+            vec![(save, vec!["test-save", "test-share"])]
+        );
+        assert!(shortcuts.has_conflict(save));
+        shortcuts.bind(1, None);
+        assert!(shortcuts.conflicts().is_empty());
+    }
+
+    #[test]
+    fn apply_config_reports_errors() {
+        let mut shortcuts = Shortcuts::default()
+            .register(
+                1,
+                "save",
+                "save",
+                Some(shortcut(Modifiers::COMMAND, Key::S)),
+            )
+            .register(
+                2,
+                "quit",
+                "quit",
+                Some(shortcut(Modifiers::COMMAND, Key::Q)),
+            );
+        let config = BTreeMap::from([
+            ("save".to_owned(), "Ctrl+Shift+S".to_owned()),
+            ("quit".to_owned(), "Ctrl+Nope".to_owned()),
+            ("open".to_owned(), "Ctrl+O".to_owned()),
+        ]);
+        let errors = shortcuts.apply_config(&config);
+        assert_eq!(errors.len(), 2, "{errors:?}");
+        assert!(errors
+            .iter()
+            .any(|err| err.contains("Unknown command 'open'")));
+        assert!(errors.iter().any(|err| err.starts_with("quit:")));
+        // 有效的配置项照常生效, 无效的保持原样
+        assert_eq!(
+            shortcuts.shortcut(1),
+            Some(shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::S))
+        );
+        assert_eq!(
+            shortcuts.shortcut(2),
+            Some(shortcut(Modifiers::COMMAND, Key::Q))
+        );
+
+        // 空字符串取消绑定, to_config 只保存修改过的绑定
+        let config = BTreeMap::from([("quit".to_owned(), String::new())]);
+        assert!(shortcuts.apply_config(&config).is_empty());
+        assert_eq!(shortcuts.shortcut(2), None);
+        assert_eq!(
+            shortcuts.to_config(),
+            BTreeMap::from([
+                ("quit".to_owned(), String::new()),
+                ("save".to_owned(), "Ctrl+Shift+S".to_owned()),
+            ])
+        );
+    }
+
+    #[test]
+    fn fuzzy_match_ranking() {
+        assert_eq!(fuzzy_match("xyz", "Quit"), None);
+        assert_eq!(fuzzy_match("tq", "Quit"), None);
+        assert!(fuzzy_match("", "Quit").is_some());
+        // 连续匹配优先
+        assert!(fuzzy_match("quit", "Quit") > fuzzy_match("qt", "Quit"));
+        // 单词开头优先
+        assert!(fuzzy_match("tt", "Toggle theme") > fuzzy_match("tt", "Settings"));
+        // 得分相同时较短的文本优先
+        assert!(fuzzy_match("save", "Save") > fuzzy_match("save", "Save as"));
+    }
+
+    #[test]
+    fn cancel_recording_restores_shortcuts() {
+        let mut shortcuts = Shortcuts::default().register(
+            1,
+            "palette",
+            "palette",
+            Some(shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::P)),
+        );
+        let ctx = egui::Context::default();
+        let modifiers = Modifiers::COMMAND | Modifiers::SHIFT;
+        assert_eq!(press(&ctx, &shortcuts, modifiers, Key::P), vec![1]);
+        shortcuts.recording = Some(0);
+        assert!(press(&ctx, &shortcuts, modifiers, Key::P).is_empty());
+        shortcuts.cancel_recording();
+        assert_eq!(press(&ctx, &shortcuts, modifiers, Key::P), vec![1]);
+    }
+}
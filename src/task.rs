@@ -0,0 +1,515 @@
+use std::any::Any;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use egui::Ui;
+
+use crate::a11y::Accessible;
+use crate::i18n::tr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct TaskId(u64);
+
+// 取消标记, 可以复制给任务内部的其他线程
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// 任务发现自己被取消时返回, 配合 TaskContext::checkpoint 使用 ?
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TaskError {
+    Cancelled,
+    // panic 的信息, 线程池的线程不受影响
+    Panicked(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskState {
+    Queued,
+    Running,
+    Done,
+    Cancelled,
+    Panicked(String),
+}
+
+impl TaskState {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+// 任务列表显示的状态, 由任务线程更新
+struct TaskShared {
+    id: TaskId,
+    name: String,
+    cancel: CancellationToken,
+    status: Mutex<(TaskState, Option<f32>)>,
+}
+
+impl TaskShared {
+    fn state(&self) -> TaskState {
+        self.status.lock().expect("task status poisoned").0.clone()
+    }
+
+    fn fraction(&self) -> Option<f32> {
+        self.status.lock().expect("task status poisoned").1
+    }
+
+    fn set_state(&self, state: TaskState) {
+        self.status.lock().expect("task status poisoned").0 = state;
+    }
+
+    fn set_fraction(&self, fraction: f32) {
+        self.status.lock().expect("task status poisoned").1 = Some(fraction.clamp(0.0, 1.0));
+    }
+}
+
+// 传给任务的上下文, 用于报告进度和检查是否被取消
+pub struct TaskContext<P> {
+    shared: Arc<TaskShared>,
+    progress: mpsc::Sender<P>,
+    egui_ctx: egui::Context,
+}
+
+impl<P> TaskContext<P> {
+    pub fn id(&self) -> TaskId {
+        self.shared.id
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.shared.cancel.is_cancelled()
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.shared.cancel.clone()
+    }
+
+    // 被取消时返回 Err, 任务可以直接 ?
+    pub fn checkpoint(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    // fraction 为 0..=1 的完成度, 显示在任务列表中. 会触发界面重绘
+    pub fn progress(&self, fraction: f32, progress: P) {
+        self.shared.set_fraction(fraction);
+        let _ = self.progress.send(progress);
+        self.egui_ctx.request_repaint();
+    }
+}
+
+// 在 update 中轮询任务的进度和结果. 丢弃句柄不会取消任务
+pub struct TaskHandle<P, T> {
+    shared: Arc<TaskShared>,
+    progress: mpsc::Receiver<P>,
+    result: mpsc::Receiver<Result<T, TaskError>>,
+    latest: Option<P>,
+}
+
+impl<P, T> TaskHandle<P, T> {
+    pub fn id(&self) -> TaskId {
+        self.shared.id
+    }
+
+    pub fn state(&self) -> TaskState {
+        self.shared.state()
+    }
+
+    pub fn fraction(&self) -> Option<f32> {
+        self.shared.fraction()
+    }
+
+    // 最新的进度, 之前的进度被丢弃
+    pub fn progress(&mut self) -> Option<&P> {
+        if let Some(progress) = self.progress.try_iter().last() {
+            self.latest = Some(progress);
+        }
+        self.latest.as_ref()
+    }
+
+    // 所有未读取的进度, 按报告顺序
+    pub fn drain_progress(&mut self) -> Vec<P> {
+        self.progress.try_iter().collect()
+    }
+
+    pub fn cancel(&self) {
+        self.shared.cancel.cancel();
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.shared.cancel.clone()
+    }
+
+    // 任务结束后返回结果, 只会返回一次
+    pub fn try_result(&mut self) -> Option<Result<T, TaskError>> {
+        self.result.try_recv().ok()
+    }
+}
+
+type Job = Box<dyn FnOnce() + Send>;
+
+struct Pool {
+    jobs: Option<mpsc::Sender<Job>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Pool {
+    fn new(threads: usize) -> Self {
+        let (jobs, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let threads = (0..threads)
+            .map(|index| {
+                let receiver = receiver.clone();
+                std::thread::Builder::new()
+                    .name(format!("TaskRunner {index}"))
+                    .spawn(move || loop {
+                        // 只在取任务时持有锁
+                        let job = receiver.lock().expect("task queue poisoned").recv();
+                        match job {
+                            Ok(job) => job(),
+                            Err(_) => break,
+                        }
+                    })
+                    .expect("failed to spawn task runner thread")
+            })
+            .collect();
+        Self {
+            jobs: Some(jobs),
+            threads,
+        }
+    }
+
+    fn execute(&self, job: Job) {
+        if let Some(jobs) = &self.jobs {
+            let _ = jobs.send(job);
+        }
+    }
+}
+
+// 关闭时等待线程退出的最长时间, 超时的线程不再等待, 以免卡住界面线程
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_millis(500);
+
+impl Drop for Pool {
+    fn drop(&mut self) {
+        self.jobs = None;
+        let deadline = Instant::now() + SHUTDOWN_TIMEOUT;
+        while Instant::now() < deadline && self.threads.iter().any(|t| !t.is_finished()) {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        // 未结束的线程在任务返回后自行退出
+        for thread in self.threads.drain(..) {
+            if thread.is_finished() {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+// panic 的参数通常是 &str 或 String, 其他类型返回 "unknown panic"
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+// 在线程池中运行后台任务. 线程池在第一次 spawn 时创建,
+// drop 时取消所有任务并短暂等待线程退出
+#[derive(Default)]
+pub struct TaskRunner {
+    // 0 表示按 CPU 核数
+    threads: usize,
+    pool: Option<Pool>,
+    tasks: Vec<Arc<TaskShared>>,
+    next_id: u64,
+}
+
+impl TaskRunner {
+    pub fn with_threads(threads: usize) -> Self {
+        Self {
+            threads,
+            pool: None,
+            tasks: Vec::new(),
+            next_id: 0,
+        }
+    }
+
+    fn pool(&mut self) -> &Pool {
+        let threads = match self.threads {
+            0 => std::thread::available_parallelism().map_or(2, |n| n.get().min(4)),
+            threads => threads,
+        };
+        self.pool.get_or_insert_with(|| Pool::new(threads))
+    }
+
+    // 任务返回 Err(Cancelled) 或 panic 时, 结果为对应的 TaskError
+    pub fn spawn<P, T>(
+        &mut self,
+        ctx: &egui::Context,
+        name: impl Into<String>,
+        job: impl FnOnce(&TaskContext<P>) -> Result<T, Cancelled> + Send + 'static,
+    ) -> TaskHandle<P, T>
+    where
+        P: Send + 'static,
+        T: Send + 'static,
+    {
+        let shared = Arc::new(TaskShared {
+            id: TaskId(self.next_id),
+            name: name.into(),
+            cancel: CancellationToken::default(),
+            status: Mutex::new((TaskState::Queued, None)),
+        });
+        self.next_id += 1;
+        self.tasks.push(shared.clone());
+
+        let (progress_tx, progress_rx) = mpsc::channel();
+        let (result_tx, result_rx) = mpsc::channel();
+        let task = TaskContext {
+            shared: shared.clone(),
+            progress: progress_tx,
+            egui_ctx: ctx.clone(),
+        };
+        self.pool().execute(Box::new(move || {
+            // 排队时已被取消的任务不再运行
+            let result = if task.is_cancelled() {
+                Err(TaskError::Cancelled)
+            } else {
+                task.shared.set_state(TaskState::Running);
+                match catch_unwind(AssertUnwindSafe(|| job(&task))) {
+                    Ok(Ok(value)) => Ok(value),
+                    Ok(Err(Cancelled)) => Err(TaskError::Cancelled),
+                    Err(payload) => Err(TaskError::Panicked(panic_message(payload.as_ref()))),
+                }
+            };
+            task.shared.set_state(match &result {
+                Ok(_) => TaskState::Done,
+                Err(TaskError::Cancelled) => TaskState::Cancelled,
+                Err(TaskError::Panicked(message)) => TaskState::Panicked(message.clone()),
+            });
+            let _ = result_tx.send(result);
+            task.egui_ctx.request_repaint();
+        }));
+
+        TaskHandle {
+            shared,
+            progress: progress_rx,
+            result: result_rx,
+            latest: None,
+        }
+    }
+
+    pub fn cancel(&self, id: TaskId) {
+        if let Some(task) = self.tasks.iter().find(|task| task.id == id) {
+            task.cancel.cancel();
+        }
+    }
+
+    pub fn running(&self) -> usize {
+        self.tasks
+            .iter()
+            .filter(|task| !task.state().is_finished())
+            .count()
+    }
+
+    pub fn clear_finished(&mut self) {
+        self.tasks.retain(|task| !task.state().is_finished());
+    }
+
+    // 任务列表: 名称、状态、进度和取消按钮
+    pub fn ui(&mut self, ui: &mut Ui) {
+        if self.tasks.is_empty() {
+            ui.weak(tr("tasks-empty"));
+            return;
+        }
+        egui::Grid::new("tasks")
+            .num_columns(3)
+            .striped(true)
+            .show(ui, |ui| {
+                for task in &self.tasks {
+                    ui.label(&task.name);
+                    match task.state() {
+                        TaskState::Queued => {
+                            ui.weak(tr("tasks-queued"));
+                        }
+                        TaskState::Running => match task.fraction() {
+                            Some(fraction) => {
+                                ui.add(
+                                    egui::ProgressBar::new(fraction)
+                                        .desired_width(160.0)
+                                        .show_percentage(),
+                                );
+                            }
+                            None => {
+                                ui.spinner();
+                            }
+                        },
+                        TaskState::Done => {
+                            ui.label(format!("✔ {}", tr("tasks-done")));
+                        }
+                        TaskState::Cancelled => {
+                            ui.weak(tr("tasks-cancelled"));
+                        }
+                        TaskState::Panicked(message) => {
+                            ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                format!("{}: {message}", tr("tasks-panicked")),
+                            );
+                        }
+                    }
+                    let cancellable = !task.state().is_finished() && !task.cancel.is_cancelled();
+                    if ui
+                        .add_enabled(cancellable, egui::Button::new("✖"))
+                        .on_hover_text(tr("tasks-cancel"))
+                        .accessible_name(tr("tasks-cancel"))
+                        .clicked()
+                    {
+                        task.cancel.cancel();
+                    }
+                    ui.end_row();
+                }
+            });
+        if ui.button(tr("tasks-clear-finished")).clicked() {
+            self.clear_finished();
+        }
+    }
+}
+
+impl Drop for TaskRunner {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.cancel.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+
+    fn wait<P, T>(handle: &TaskHandle<P, T>) -> Result<T, TaskError> {
+        handle
+            .result
+            .recv_timeout(Duration::from_secs(5))
+            .expect("task did not finish")
+    }
+
+    #[test]
+    fn progress_keeps_latest() {
+        let ctx = egui::Context::default();
+        let mut runner = TaskRunner::with_threads(1);
+        let mut handle = runner.spawn(&ctx, "count", |task| {
+            for i in 1..=3 {
+                task.progress(i as f32 / 3.0, i);
+            }
+            Ok(())
+        });
+        assert_eq!(wait(&handle), Ok(()));
+        assert_eq!(handle.state(), TaskState::Done);
+        assert_eq!(handle.fraction(), Some(1.0));
+        assert_eq!(handle.progress(), Some(&3));
+        // 已读取的进度保留, 不会再出现在 drain_progress 中
+        assert_eq!(handle.progress(), Some(&3));
+        assert!(handle.drain_progress().is_empty());
+    }
+
+    #[test]
+    fn cancelled_while_queued_never_runs() {
+        let ctx = egui::Context::default();
+        let mut runner = TaskRunner::with_threads(1);
+        let (release, gate) = mpsc::channel::<()>();
+        let blocker = runner.spawn(&ctx, "blocker", move |_: &TaskContext<()>| {
+            let _ = gate.recv();
+            Ok(())
+        });
+        let ran = Arc::new(AtomicBool::new(false));
+        let queued = runner.spawn(&ctx, "queued", {
+            let ran = ran.clone();
+            move |_: &TaskContext<()>| {
+                ran.store(true, Ordering::Relaxed);
+                Ok(())
+            }
+        });
+        assert_eq!(queued.state(), TaskState::Queued);
+        queued.cancel();
+        release.send(()).unwrap();
+        assert_eq!(wait(&blocker), Ok(()));
+        assert_eq!(wait(&queued), Err(TaskError::Cancelled));
+        assert_eq!(queued.state(), TaskState::Cancelled);
+        assert!(!ran.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn checkpoint_reports_cancelled() {
+        let ctx = egui::Context::default();
+        let mut runner = TaskRunner::with_threads(1);
+        let (started_tx, started) = mpsc::channel();
+        let handle = runner.spawn(
+            &ctx,
+            "loop",
+            move |task: &TaskContext<()>| -> Result<(), _> {
+                let _ = started_tx.send(());
+                loop {
+                    task.checkpoint()?;
+                    std::thread::sleep(Duration::from_millis(1));
+                }
+            },
+        );
+        started.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(handle.state(), TaskState::Running);
+        runner.cancel(handle.id());
+        assert_eq!(wait(&handle), Err(TaskError::Cancelled));
+        assert_eq!(handle.state(), TaskState::Cancelled);
+    }
+
+    #[test]
+    fn panic_does_not_break_pool() {
+        let ctx = egui::Context::default();
+        let mut runner = TaskRunner::with_threads(1);
+        let panicked = runner.spawn(&ctx, "panic", |_: &TaskContext<()>| -> Result<(), _> {
+            panic!("boom")
+        });
+        assert_eq!(wait(&panicked), Err(TaskError::Panicked("boom".to_owned())));
+        assert_eq!(panicked.state(), TaskState::Panicked("boom".to_owned()));
+        // 唯一的线程仍然可以运行后续任务
+        let next = runner.spawn(&ctx, "next", |_: &TaskContext<()>| Ok(42));
+        assert_eq!(wait(&next), Ok(42));
+    }
+
+    #[test]
+    fn drop_does_not_wait_for_stuck_tasks() {
+        let ctx = egui::Context::default();
+        let mut runner = TaskRunner::with_threads(1);
+        let (_release, gate) = mpsc::channel::<()>();
+        let _handle = runner.spawn(&ctx, "stuck", move |_: &TaskContext<()>| {
+            // 不检查取消, 直到测试结束
+            let _ = gate.recv_timeout(Duration::from_secs(5));
+            Ok(())
+        });
+        let start = Instant::now();
+        drop(runner);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,506 @@
+use std::io;
+use std::path::Path;
+
+use egui::{Color32, RichText, Ui, Visuals};
+
+use crate::a11y::Accessible;
+use crate::i18n::{tr, tr_args};
+use crate::toast::Toasts;
+
+// 用户选择的主题, System 跟随系统的深色/浅色设置
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+pub enum ThemePreference {
+    Dark,
+    Light,
+    #[default]
+    System,
+}
+
+impl ThemePreference {
+    pub const ALL: [Self; 3] = [Self::Dark, Self::Light, Self::System];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Dark => tr("theme-dark"),
+            Self::Light => tr("theme-light"),
+            Self::System => tr("theme-system"),
+        }
+    }
+}
+
+// 配色方案, 在 egui 默认的深色或浅色 visuals 上替换几种颜色
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+pub struct Palette {
+    pub name: String,
+    pub dark_mode: bool,
+    // 面板和窗口的背景
+    pub background: Color32,
+    // 标签等非交互文字
+    pub text: Color32,
+    // 选区等强调色
+    pub accent: Color32,
+    // 按钮等控件的背景
+    pub widget: Color32,
+}
+
+impl Palette {
+    // 与 egui 默认的 visuals 完全相同
+    fn from_visuals(name: &str, visuals: &Visuals) -> Self {
+        Self {
+            name: name.to_owned(),
+            dark_mode: visuals.dark_mode,
+            background: visuals.panel_fill,
+            text: visuals.widgets.noninteractive.fg_stroke.color,
+            accent: visuals.selection.bg_fill,
+            widget: visuals.widgets.inactive.weak_bg_fill,
+        }
+    }
+
+    pub fn egui_dark() -> Self {
+        Self::from_visuals("egui dark", &Visuals::dark())
+    }
+
+    pub fn egui_light() -> Self {
+        Self::from_visuals("egui light", &Visuals::light())
+    }
+
+    pub fn builtin() -> Vec<Self> {
+        vec![
+            Self::egui_dark(),
+            Self::egui_light(),
+            Self {
+                name: "Solarized dark".to_owned(),
+                dark_mode: true,
+                background: Color32::from_rgb(0, 43, 54),
+                text: Color32::from_rgb(147, 161, 161),
+                accent: Color32::from_rgb(38, 139, 210),
+                widget: Color32::from_rgb(7, 54, 66),
+            },
+            Self {
+                name: "Solarized light".to_owned(),
+                dark_mode: false,
+                background: Color32::from_rgb(253, 246, 227),
+                text: Color32::from_rgb(88, 110, 117),
+                accent: Color32::from_rgb(181, 211, 237),
+                widget: Color32::from_rgb(238, 232, 213),
+            },
+        ]
+    }
+
+    pub fn visuals(&self) -> Visuals {
+        let mut visuals = if self.dark_mode {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+        visuals.panel_fill = self.background;
+        visuals.window_fill = self.background;
+        visuals.selection.bg_fill = self.accent;
+        let widgets = &mut visuals.widgets;
+        widgets.noninteractive.fg_stroke.color = self.text;
+        widgets.inactive.weak_bg_fill = self.widget;
+        widgets.inactive.bg_fill = self.widget;
+        visuals
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let json = std::fs::read_to_string(path)?;
+        serde_json::from_str(&json).map_err(io::Error::from)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::from)?;
+        std::fs::write(path, json)
+    }
+}
+
+// 主题设置, 深色和浅色模式分别选择一个配色方案
+#[derive(Clone, Debug, PartialEq, serde::Deserialize, serde::Serialize)]
+#[serde(default)]
+pub struct ThemeSettings {
+    pub preference: ThemePreference,
+    pub dark_palette: String,
+    pub light_palette: String,
+    // 用户自定义的配色方案, 内置的不保存
+    pub custom: Vec<Palette>,
+    #[serde(skip)]
+    editing: Option<usize>,
+}
+
+impl Default for ThemeSettings {
+    fn default() -> Self {
+        Self {
+            preference: ThemePreference::default(),
+            dark_palette: Palette::egui_dark().name,
+            light_palette: Palette::egui_light().name,
+            custom: Vec::new(),
+            editing: None,
+        }
+    }
+}
+
+impl ThemeSettings {
+    // 系统主题未知时 (如 Linux 或无界面运行) 使用深色
+    pub fn is_dark(&self, system: Option<eframe::Theme>) -> bool {
+        match self.preference {
+            ThemePreference::Dark => true,
+            ThemePreference::Light => false,
+            ThemePreference::System => system != Some(eframe::Theme::Light),
+        }
+    }
+
+    // 在深色和浅色之间切换, 不再跟随系统
+    pub fn toggle(&mut self, system: Option<eframe::Theme>) {
+        self.preference = if self.is_dark(system) {
+            ThemePreference::Light
+        } else {
+            ThemePreference::Dark
+        };
+    }
+
+    // 内置的在前, 名称相同时内置的优先
+    pub fn palettes(&self) -> Vec<Palette> {
+        let mut palettes = Palette::builtin();
+        palettes.extend(self.custom.iter().cloned());
+        palettes
+    }
+
+    // 选择的配色方案不存在或深浅不符时使用 egui 默认的
+    pub fn palette(&self, dark_mode: bool) -> Palette {
+        let name = if dark_mode {
+            &self.dark_palette
+        } else {
+            &self.light_palette
+        };
+        self.palettes()
+            .into_iter()
+            .find(|palette| &palette.name == name && palette.dark_mode == dark_mode)
+            .unwrap_or_else(|| {
+                if dark_mode {
+                    Palette::egui_dark()
+                } else {
+                    Palette::egui_light()
+                }
+            })
+    }
+
+    pub fn visuals(&self, system: Option<eframe::Theme>) -> Visuals {
+        self.palette(self.is_dark(system)).visuals()
+    }
+
+    fn unique_name(&self, base: &str) -> String {
+        let taken = |name: &str| self.palettes().iter().any(|p| p.name == name);
+        if !taken(base) {
+            return base.to_owned();
+        }
+        (2..)
+            .map(|n| format!("{base} ({n})"))
+            .find(|name| !taken(name))
+            .expect("infinite iterator")
+    }
+
+    // 添加自定义配色方案 (名称重复时自动改名) 并在对应模式下使用它
+    pub fn add_palette(&mut self, mut palette: Palette) -> usize {
+        palette.name = self.unique_name(&palette.name);
+        self.select(&palette);
+        self.custom.push(palette);
+        self.custom.len() - 1
+    }
+
+    // 自定义配色方案改名, 名称为空时保持不变, 重复时和 add_palette 一样自动改名.
+    // 选中它的模式继续使用它. 返回最终的名称
+    pub fn rename(&mut self, index: usize, name: &str) -> String {
+        let old_name = self.custom[index].name.clone();
+        let name = name.trim();
+        if name.is_empty() || name == old_name {
+            return old_name;
+        }
+        let name = self.unique_name(name);
+        if self.dark_palette == old_name {
+            self.dark_palette = name.clone();
+        }
+        if self.light_palette == old_name {
+            self.light_palette = name.clone();
+        }
+        self.custom[index].name = name.clone();
+        name
+    }
+
+    fn select(&mut self, palette: &Palette) {
+        if palette.dark_mode {
+            self.dark_palette = palette.name.clone();
+        } else {
+            self.light_palette = palette.name.clone();
+        }
+    }
+
+    pub fn import(&mut self, path: &Path) -> io::Result<String> {
+        let index = self.add_palette(Palette::load(path)?);
+        self.editing = Some(index);
+        Ok(self.custom[index].name.clone())
+    }
+
+    // 主题设置面板, 返回是否有修改. 编辑的配色方案立即生效并显示预览
+    pub fn ui(&mut self, ui: &mut Ui, system: Option<eframe::Theme>, toasts: &mut Toasts) -> bool {
+        let before = self.clone();
+        ui.horizontal(|ui| {
+            ui.label(tr("theme"));
+            for preference in ThemePreference::ALL {
+                ui.selectable_value(&mut self.preference, preference, preference.name());
+            }
+        });
+
+        let palettes = self.palettes();
+        egui::Grid::new("theme_palettes")
+            .num_columns(2)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                for (dark_mode, key) in
+                    [(true, "theme-dark-palette"), (false, "theme-light-palette")]
+                {
+                    let label = ui.label(tr(key));
+                    let selected = if dark_mode {
+                        &mut self.dark_palette
+                    } else {
+                        &mut self.light_palette
+                    };
+                    egui::ComboBox::from_id_source(key)
+                        .selected_text(selected.as_str())
+                        .show_ui(ui, |ui| {
+                            for palette in palettes.iter().filter(|p| p.dark_mode == dark_mode) {
+                                ui.selectable_value(
+                                    selected,
+                                    palette.name.clone(),
+                                    palette.name.as_str(),
+                                );
+                            }
+                        })
+                        .response
+                        .labelled_by(label.id);
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(8.0);
+        ui.label(RichText::new(tr("theme-custom-palettes")).strong());
+        self.editing = self.editing.filter(|index| *index < self.custom.len());
+        ui.horizontal(|ui| {
+            let editing = self.editing.map_or_else(
+                || tr("theme-no-custom").to_owned(),
+                |i| self.custom[i].name.clone(),
+            );
+            egui::ComboBox::from_id_source("theme_editing")
+                .selected_text(editing)
+                .show_ui(ui, |ui| {
+                    for (index, palette) in self.custom.iter().enumerate() {
+                        ui.selectable_value(&mut self.editing, Some(index), palette.name.as_str());
+                    }
+                })
+                .response
+                .on_hover_text(tr("theme-edit"))
+                .accessible_name(tr("theme-edit"));
+            if ui.button(tr("theme-new")).clicked() {
+                // 以当前使用的配色方案为起点
+                let mut palette = self.palette(self.is_dark(system));
+                palette.name = tr("theme-custom-name").to_owned();
+                self.editing = Some(self.add_palette(palette));
+            }
+            if ui
+                .add_enabled(
+                    self.editing.is_some(),
+                    egui::Button::new(tr("theme-delete")),
+                )
+                .clicked()
+            {
+                if let Some(index) = self.editing.take() {
+                    self.custom.remove(index);
+                }
+            }
+            if ui.button(tr("theme-import")).clicked() {
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .pick_file()
+                {
+                    match self.import(&path) {
+                        Ok(name) => toasts.success(tr_args("theme-imported", &[("name", &name)])),
+                        Err(err) => toasts.error(tr_args(
+                            "file-open-failed",
+                            &[("path", &path.display()), ("error", &err)],
+                        )),
+                    }
+                }
+            }
+            if ui
+                .add_enabled(
+                    self.editing.is_some(),
+                    egui::Button::new(tr("theme-export")),
+                )
+                .clicked()
+            {
+                let palette = &self.custom[self.editing.expect("checked above")];
+                if let Some(path) = rfd::FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name(&format!("{}.json", palette.name))
+                    .save_file()
+                {
+                    match palette.save(&path) {
+                        Ok(()) => {
+                            toasts.success(tr_args("file-saved", &[("path", &path.display())]))
+                        }
+                        Err(err) => toasts.error(tr_args(
+                            "file-save-failed",
+                            &[("path", &path.display()), ("error", &err)],
+                        )),
+                    }
+                }
+            }
+        });
+
+        if let Some(index) = self.editing {
+            self.palette_editor(ui, index);
+        }
+        *self != before
+    }
+
+    fn palette_editor(&mut self, ui: &mut Ui, index: usize) {
+        // 输入的名称在编辑结束时才生效, 以便检查重名
+        let name_id = ui.make_persistent_id(("theme_name", index));
+        let mut name = ui
+            .data_mut(|data| data.get_temp::<String>(name_id))
+            .unwrap_or_else(|| self.custom[index].name.clone());
+        let mut rename = false;
+        let palette = &mut self.custom[index];
+        egui::Grid::new("theme_editor")
+            .num_columns(2)
+            .spacing([12.0, 8.0])
+            .show(ui, |ui| {
+                let label = ui.label(tr("theme-name"));
+                let response = ui.text_edit_singleline(&mut name).labelled_by(label.id);
+                if response.lost_focus() {
+                    rename = true;
+                } else if response.changed() {
+                    ui.data_mut(|data| data.insert_temp(name_id, name.clone()));
+                }
+                ui.end_row();
+
+                ui.label("");
+                ui.checkbox(&mut palette.dark_mode, tr("theme-dark"));
+                ui.end_row();
+
+                for (color, key) in [
+                    (&mut palette.background, "theme-background"),
+                    (&mut palette.text, "theme-text"),
+                    (&mut palette.accent, "theme-accent"),
+                    (&mut palette.widget, "theme-widget"),
+                ] {
+                    let label = ui.label(tr(key));
+                    ui.color_edit_button_srgba(color).labelled_by(label.id);
+                    ui.end_row();
+                }
+            });
+
+        if rename {
+            ui.data_mut(|data| data.remove::<String>(name_id));
+            self.rename(index, &name);
+        }
+        // 切换深浅后, 之前选中它的模式继续使用它
+        let palette = self.custom[index].clone();
+        let was_selected = self.dark_palette == palette.name || self.light_palette == palette.name;
+        if self.dark_palette == palette.name {
+            self.dark_palette = Palette::egui_dark().name;
+        }
+        if self.light_palette == palette.name {
+            self.light_palette = Palette::egui_light().name;
+        }
+        if was_selected {
+            self.select(&palette);
+        }
+
+        ui.add_space(4.0);
+        ui.label(tr("theme-preview"));
+        preview(ui, &palette);
+    }
+}
+
+// 用配色方案显示几个示例控件, 不影响界面的其他部分
+fn preview(ui: &mut Ui, palette: &Palette) {
+    ui.scope(|ui| {
+        ui.style_mut().visuals = palette.visuals();
+        egui::Frame::group(ui.style())
+            .fill(palette.background)
+            .show(ui, |ui| {
+                ui.label(tr("theme-preview-text"));
+                ui.horizontal(|ui| {
+                    let _ = ui.button(tr("theme-preview-button"));
+                    let mut checked = true;
+                    ui.checkbox(&mut checked, tr("theme-preview-checkbox"));
+                });
+                // 选中的标签使用强调色
+                let _ = ui.selectable_label(true, tr("theme-accent"));
+            });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(name: &str, dark_mode: bool) -> Palette {
+        Palette {
+            name: name.to_owned(),
+            dark_mode,
+            ..Palette::egui_dark()
+        }
+    }
+
+    #[test]
+    fn add_selects_with_unique_name() {
+        let mut theme = ThemeSettings::default();
+        let index = theme.add_palette(custom("Solarized dark", true));
+        assert_eq!(theme.custom[index].name, "Solarized dark (2)");
+        assert_eq!(theme.dark_palette, "Solarized dark (2)");
+        assert_eq!(theme.light_palette, "egui light");
+        assert_eq!(theme.palette(true), theme.custom[index]);
+        // 深浅不符时使用 egui 默认的
+        theme.light_palette = "Solarized dark (2)".to_owned();
+        assert_eq!(theme.palette(false), Palette::egui_light());
+    }
+
+    #[test]
+    fn rename_keeps_names_unique() {
+        let mut theme = ThemeSettings::default();
+        let mine = theme.add_palette(custom("Mine", true));
+        let other = theme.add_palette(custom("Other", false));
+        assert_eq!(theme.dark_palette, "Mine");
+
+        assert_eq!(theme.rename(mine, "Other"), "Other (2)");
+        assert_eq!(theme.rename(mine, "egui dark"), "egui dark (2)");
+        // 选中它的模式跟随改名
+        assert_eq!(theme.dark_palette, "egui dark (2)");
+        assert_eq!(theme.palette(true).name, "egui dark (2)");
+        // 空名称和原名称不变
+        assert_eq!(theme.rename(mine, "  "), "egui dark (2)");
+        assert_eq!(theme.rename(other, "Other"), "Other");
+        assert_eq!(theme.rename(other, " Light "), "Light");
+        assert_eq!(theme.light_palette, "Light");
+    }
+
+    #[test]
+    fn import_adds_and_edits() {
+        let path = std::env::temp_dir().join(format!("theme-import-{}.json", std::process::id()));
+        let mut palette = Palette::egui_light();
+        palette.accent = Color32::RED;
+        palette.save(&path).unwrap();
+
+        let mut theme = ThemeSettings::default();
+        let name = theme.import(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(name, "egui light (2)");
+        assert_eq!(theme.editing, Some(0));
+        assert_eq!(theme.light_palette, name);
+        assert_eq!(theme.palette(false).accent, Color32::RED);
+
+        assert!(theme.import(&path).is_err());
+        assert_eq!(theme.custom.len(), 1);
+    }
+}
@@ -0,0 +1,260 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use egui::{Response, Ui, Widget};
+
+// 单调时钟, 不受系统时间调整的影响. 测试时可以换成 FakeClock
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// 手动推进的时钟, 复制出的时钟共享同一个时间
+#[derive(Clone, Debug)]
+pub struct FakeClock(Arc<Mutex<Instant>>);
+
+impl Default for FakeClock {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+impl FakeClock {
+    pub fn new(start: Instant) -> Self {
+        Self(Arc::new(Mutex::new(start)))
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.0.lock().expect("fake clock poisoned") += duration;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().expect("fake clock poisoned")
+    }
+}
+
+// 按 key 管理的一组定时器, 每个 key 最多一个到期时间
+#[derive(Clone, Debug)]
+pub struct Timers<K> {
+    deadlines: Vec<(K, Instant)>,
+}
+
+impl<K> Default for Timers<K> {
+    fn default() -> Self {
+        Self {
+            deadlines: Vec::new(),
+        }
+    }
+}
+
+impl<K: PartialEq> Timers<K> {
+    // 替换 key 原来的到期时间
+    pub fn schedule_at(&mut self, key: K, at: Instant) {
+        self.cancel(&key);
+        self.deadlines.push((key, at));
+    }
+
+    pub fn schedule_in(&mut self, clock: &impl Clock, key: K, after: Duration) {
+        self.schedule_at(key, clock.now() + after);
+    }
+
+    pub fn cancel(&mut self, key: &K) {
+        self.deadlines.retain(|(other, _)| other != key);
+    }
+
+    pub fn clear(&mut self) {
+        self.deadlines.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.deadlines.is_empty()
+    }
+
+    pub fn deadline(&self, key: &K) -> Option<Instant> {
+        self.deadlines
+            .iter()
+            .find(|(other, _)| other == key)
+            .map(|(_, at)| *at)
+    }
+
+    pub fn remaining(&self, clock: &impl Clock, key: &K) -> Option<Duration> {
+        Some(self.deadline(key)?.saturating_duration_since(clock.now()))
+    }
+
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.deadlines.iter().map(|(_, at)| *at).min()
+    }
+
+    // 取出所有到期的 key, 按到期时间排序
+    pub fn pop_due(&mut self, now: Instant) -> Vec<K> {
+        let mut due = Vec::new();
+        let mut index = 0;
+        while index < self.deadlines.len() {
+            if self.deadlines[index].1 <= now {
+                due.push(self.deadlines.swap_remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        due.sort_by_key(|(_, at)| *at);
+        due.into_iter().map(|(key, _)| key).collect()
+    }
+}
+
+// 只在需要时唤醒界面, 空闲时不会每秒重绘
+pub fn request_repaint_at(ctx: &egui::Context, now: Instant, deadline: Option<Instant>) {
+    if let Some(deadline) = deadline {
+        ctx.request_repaint_after(deadline.saturating_duration_since(now));
+    }
+}
+
+// 剩余时间向上取整到秒, 一分钟以上显示为 m:ss
+pub fn format_remaining(remaining: Duration) -> String {
+    let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    if secs >= 60 {
+        format!("{}:{:02}", secs / 60, secs % 60)
+    } else {
+        format!("{secs} s")
+    }
+}
+
+// 显示的秒数下一次变化前的时间
+fn until_next_second(remaining: Duration) -> Duration {
+    match remaining.subsec_nanos() {
+        0 => Duration::from_secs(1).min(remaining),
+        nanos => Duration::from_nanos(u64::from(nanos)),
+    }
+}
+
+// 倒计时标签, 可选进度条. 只在显示的秒数变化时请求重绘
+pub struct Countdown {
+    remaining: Duration,
+    total: Option<Duration>,
+    prefix: String,
+}
+
+impl Countdown {
+    pub fn new(remaining: Duration) -> Self {
+        Self {
+            remaining,
+            total: None,
+            prefix: String::new(),
+        }
+    }
+
+    pub fn until(clock: &impl Clock, deadline: Instant) -> Self {
+        Self::new(deadline.saturating_duration_since(clock.now()))
+    }
+
+    // 设置总时长后显示进度条
+    pub fn total(mut self, total: Duration) -> Self {
+        self.total = Some(total);
+        self
+    }
+
+    pub fn prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+}
+
+impl Widget for Countdown {
+    fn ui(self, ui: &mut Ui) -> Response {
+        if !self.remaining.is_zero() {
+            ui.ctx()
+                .request_repaint_after(until_next_second(self.remaining));
+        }
+        let text = format!("{}{}", self.prefix, format_remaining(self.remaining));
+        match self.total.filter(|total| !total.is_zero()) {
+            Some(total) => {
+                let done = 1.0 - self.remaining.as_secs_f32() / total.as_secs_f32();
+                ui.add(egui::ProgressBar::new(done.clamp(0.0, 1.0)).text(text))
+            }
+            None => ui.label(text),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pop_due_in_deadline_order() {
+        let clock = FakeClock::default();
+        let start = clock.now();
+        let mut timers = Timers::default();
+        timers.schedule_in(&clock, "c", Duration::from_secs(3));
+        timers.schedule_in(&clock, "a", Duration::from_secs(1));
+        timers.schedule_in(&clock, "later", Duration::from_secs(10));
+        timers.schedule_in(&clock, "b", Duration::from_secs(2));
+        assert_eq!(timers.next_deadline(), Some(start + Duration::from_secs(1)));
+        assert_eq!(timers.pop_due(start), Vec::<&str>::new());
+
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(timers.pop_due(clock.now()), vec!["a", "b", "c"]);
+        assert_eq!(timers.pop_due(clock.now()), Vec::<&str>::new());
+        assert_eq!(
+            timers.next_deadline(),
+            Some(start + Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn reschedule_replaces_deadline() {
+        let clock = FakeClock::default();
+        let mut timers = Timers::default();
+        timers.schedule_in(&clock, "a", Duration::from_secs(1));
+        timers.schedule_in(&clock, "a", Duration::from_secs(5));
+        assert_eq!(timers.remaining(&clock, &"a"), Some(Duration::from_secs(5)));
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(timers.pop_due(clock.now()), Vec::<&str>::new());
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(timers.pop_due(clock.now()), vec!["a"]);
+        assert!(timers.is_empty());
+    }
+
+    #[test]
+    fn format_remaining_rounds_up() {
+        let format = |secs: f64| format_remaining(Duration::from_secs_f64(secs));
+        assert_eq!(format(0.0), "0 s");
+        assert_eq!(format(0.5), "1 s");
+        assert_eq!(format(59.5), "1:00");
+        assert_eq!(format(60.0), "1:00");
+        assert_eq!(format(61.0), "1:01");
+    }
+
+    #[test]
+    fn until_next_second_is_never_zero() {
+        for remaining in [
+            Duration::from_nanos(1),
+            Duration::from_millis(500),
+            Duration::from_secs(1),
+            Duration::from_millis(1500),
+            Duration::from_secs(60),
+        ] {
+            let wait = until_next_second(remaining);
+            assert!(!wait.is_zero(), "{remaining:?}");
+            assert!(
+                wait <= remaining.min(Duration::from_secs(1)),
+                "{remaining:?}"
+            );
+            // 等待之后显示的秒数变化
+            assert_ne!(
+                format_remaining(remaining),
+                format_remaining(remaining - wait),
+                "{remaining:?}"
+            );
+        }
+    }
+}
@@ -0,0 +1,159 @@
+use egui::{Align2, Color32, Id, RichText};
+
+use crate::a11y::Accessible;
+use crate::i18n::tr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Severity {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn icon(self) -> &'static str {
+        match self {
+            Severity::Info => "ℹ",
+            Severity::Success => "✔",
+            Severity::Warning => "⚠",
+            Severity::Error => "❌",
+        }
+    }
+
+    fn color(self, visuals: &egui::Visuals) -> Color32 {
+        match self {
+            Severity::Info => visuals.hyperlink_color,
+            Severity::Success => Color32::from_rgb(0, 180, 80),
+            Severity::Warning => visuals.warn_fg_color,
+            Severity::Error => visuals.error_fg_color,
+        }
+    }
+
+    // 错误默认停留更久
+    fn default_duration(self) -> f64 {
+        match self {
+            Severity::Info | Severity::Success => 3.0,
+            Severity::Warning => 5.0,
+            Severity::Error => 8.0,
+        }
+    }
+}
+
+struct Toast {
+    id: u64,
+    severity: Severity,
+    text: String,
+    // 停留秒数, None 表示需要手动关闭
+    duration: Option<f64>,
+    // 第一次显示的时间
+    shown_at: Option<f64>,
+}
+
+// 右下角堆叠的通知
+pub struct Toasts {
+    toasts: Vec<Toast>,
+    next_id: u64,
+    // 同时显示的最大数量, 其余的排队
+    max_visible: usize,
+}
+
+impl Default for Toasts {
+    fn default() -> Self {
+        Self {
+            toasts: Vec::new(),
+            next_id: 0,
+            max_visible: 5,
+        }
+    }
+}
+
+impl Toasts {
+    pub fn add(&mut self, severity: Severity, text: impl Into<String>, duration: Option<f64>) {
+        self.toasts.push(Toast {
+            id: self.next_id,
+            severity,
+            text: text.into(),
+            duration,
+            shown_at: None,
+        });
+        self.next_id += 1;
+    }
+
+    pub fn info(&mut self, text: impl Into<String>) {
+        let duration = Severity::Info.default_duration();
+        self.add(Severity::Info, text, Some(duration));
+    }
+
+    pub fn success(&mut self, text: impl Into<String>) {
+        let duration = Severity::Success.default_duration();
+        self.add(Severity::Success, text, Some(duration));
+    }
+
+    pub fn warning(&mut self, text: impl Into<String>) {
+        let duration = Severity::Warning.default_duration();
+        self.add(Severity::Warning, text, Some(duration));
+    }
+
+    pub fn error(&mut self, text: impl Into<String>) {
+        let duration = Severity::Error.default_duration();
+        self.add(Severity::Error, text, Some(duration));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        let now = ctx.input(|i| i.time);
+
+        // 移除超时的通知
+        self.toasts
+            .retain(|toast| match (toast.duration, toast.shown_at) {
+                (Some(duration), Some(shown_at)) => now - shown_at < duration,
+                _ => true,
+            });
+
+        let mut offset = egui::vec2(-8.0, -8.0);
+        let mut dismissed = None;
+        let mut next_expiry: Option<f64> = None;
+        for toast in self.toasts.iter_mut().take(self.max_visible) {
+            let shown_at = *toast.shown_at.get_or_insert(now);
+            if let Some(duration) = toast.duration {
+                let remaining = shown_at + duration - now;
+                next_expiry = Some(next_expiry.map_or(remaining, |t| t.min(remaining)));
+            }
+
+            let response = egui::Area::new(Id::new(("toast", toast.id)))
+                .anchor(Align2::RIGHT_BOTTOM, offset)
+                .order(egui::Order::Foreground)
+                .interactable(true)
+                .show(ctx, |ui| {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            let color = toast.severity.color(ui.visuals());
+                            ui.label(RichText::new(toast.severity.icon()).color(color));
+                            ui.label(toast.text.as_str());
+                            if ui
+                                .small_button("✖")
+                                .accessible_name(tr("toast-dismiss"))
+                                .clicked()
+                            {
+                                dismissed = Some(toast.id);
+                            }
+                        });
+                    });
+                })
+                .response;
+            offset.y -= response.rect.height() + 4.0;
+        }
+
+        if let Some(id) = dismissed {
+            self.toasts.retain(|toast| toast.id != id);
+        }
+        // 只在下一条通知到期时重绘
+        if let Some(remaining) = next_expiry {
+            ctx.request_repaint_after(std::time::Duration::from_secs_f64(remaining.max(0.0)));
+        }
+    }
+}